@@ -0,0 +1,277 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use poise::serenity_prelude::{self as serenity, ChannelId};
+use crate::admin::{Rules, Source, PREFIXES, RULES};
+use crate::base::Data;
+use crate::core::Level;
+use crate::helper::{self, Context, Error, Result};
+use crate::storage::Scope;
+
+const MAX_PREFIXES: usize = 8;
+const MAX_PREFIX_LENGTH: usize = 16;
+const TOGGLES_PER_PAGE: usize = 20;
+
+/// Guild settings for members who can manage the guild
+#[poise::command(
+    prefix_command, guild_only,
+    custom_data = "Level::GuildAdmin",
+    rename = "admin",
+    subcommands("prefix", "toggle"),
+)]
+pub async fn group(_: Context<'_>) -> Result<()> {
+    Ok(())
+}
+
+/// Show the command prefixes of this guild
+#[poise::command(
+    prefix_command, guild_only,
+    custom_data = "Level::GuildAdmin",
+    subcommands("list", "set", "reset"),
+)]
+pub async fn prefix(context: Context<'_>) -> Result<()> {
+    list_prefixes(context).await
+}
+
+/// Show the command prefixes of this guild
+#[poise::command(prefix_command, guild_only, custom_data = "Level::GuildAdmin")]
+pub async fn list(context: Context<'_>) -> Result<()> {
+    list_prefixes(context).await
+}
+
+/// Set the command prefixes of this guild
+#[poise::command(prefix_command, guild_only, custom_data = "Level::GuildAdmin")]
+pub async fn set(
+    context: Context<'_>,
+    #[description = "Up to 8 prefixes separated by spaces"] prefixes: Vec<String>,
+) -> Result<()> {
+    if prefixes.is_empty() || prefixes.len() > MAX_PREFIXES {
+        let response = format!("\"prefixes\" parameter must have 1 to {MAX_PREFIXES} prefixes!");
+        context.say(response).await?;
+        return Ok(())
+    }
+
+    if prefixes.iter().any(|prefix| prefix.chars().count() > MAX_PREFIX_LENGTH) {
+        let response = format!("prefixes must be at most {MAX_PREFIX_LENGTH} characters!");
+        context.say(response).await?;
+        return Ok(())
+    }
+
+    let scope = Scope::Guild(context.guild_id().unwrap());
+    let response = format!("setting prefixes to {}...", quote(&prefixes));
+
+    context.say(response).await?;
+    context.data().storage.set(scope, &PREFIXES, &prefixes).await?;
+
+    Ok(())
+}
+
+/// Reset the command prefixes of this guild to the default
+#[poise::command(prefix_command, guild_only, custom_data = "Level::GuildAdmin")]
+pub async fn reset(context: Context<'_>) -> Result<()> {
+    let scope = Scope::Guild(context.guild_id().unwrap());
+
+    context.say("resetting prefixes to default...").await?;
+    context.data().storage.remove(scope, &PREFIXES).await?;
+
+    Ok(())
+}
+
+/// Show which commands are enabled in this channel
+#[poise::command(
+    prefix_command, guild_only, custom_data = "Level::GuildAdmin",
+    rename = "command",
+    subcommands("toggle_list", "toggle_enable", "toggle_disable", "toggle_reset"),
+)]
+pub async fn toggle(context: Context<'_>) -> Result<()> {
+    list_toggles(context).await
+}
+
+/// Show which commands are enabled in this channel
+#[poise::command(prefix_command, guild_only, custom_data = "Level::GuildAdmin", rename = "list")]
+pub async fn toggle_list(context: Context<'_>) -> Result<()> {
+    list_toggles(context).await
+}
+
+/// Enable a command or group in this guild or a channel
+#[poise::command(prefix_command, guild_only, custom_data = "Level::GuildAdmin", rename = "enable")]
+pub async fn toggle_enable(
+    context: Context<'_>,
+    #[description = "Command or group name, then a channel mention or id for just that channel"]
+    #[rest]
+    arguments: String,
+) -> Result<()> {
+    set_toggle(context, arguments, Some(true)).await
+}
+
+/// Disable a command or group in this guild or a channel
+#[poise::command(prefix_command, guild_only, custom_data = "Level::GuildAdmin", rename = "disable")]
+pub async fn toggle_disable(
+    context: Context<'_>,
+    #[description = "Command or group name, then a channel mention or id for just that channel"]
+    #[rest]
+    arguments: String,
+) -> Result<()> {
+    set_toggle(context, arguments, Some(false)).await
+}
+
+/// Remove a command toggle in this guild or a channel
+#[poise::command(prefix_command, guild_only, custom_data = "Level::GuildAdmin", rename = "reset")]
+pub async fn toggle_reset(
+    context: Context<'_>,
+    #[description = "Command or group name, then a channel mention or id for just that channel"]
+    #[rest]
+    arguments: String,
+) -> Result<()> {
+    set_toggle(context, arguments, None).await
+}
+
+/// `enabled` of `None` removes the rule, falling back to the wider scope.
+///
+/// The channel is only taken from a trailing mention or id, so a channel
+/// named like a command can never be mistaken for one.
+async fn set_toggle(context: Context<'_>, arguments: String, enabled: Option<bool>) -> Result<()> {
+    let mut words = arguments.split_whitespace().collect::<Vec<&str>>();
+    let channel_id = match words.last().and_then(|word| parse_channel(word)) {
+        Some(channel_id) => {
+            words.pop();
+            Some(channel_id)
+        }
+        None => None,
+    };
+    let name = words.join(" ");
+
+    let guild_id = context.guild_id().unwrap();
+    if let Some(channel_id) = channel_id {
+        let channel = context.serenity_context().cache.guild_channel(channel_id);
+        if channel.map(|channel| channel.guild_id) != Some(guild_id) {
+            context.say(format!("there is no channel <#{channel_id}> in this guild!")).await?;
+            return Ok(())
+        }
+    }
+
+    if !command_names(context).contains(&name) {
+        context.say(format!("there is no command or group named `{name}`!")).await?;
+        return Ok(())
+    }
+
+    if name == "admin" || name.starts_with("admin ") {
+        context.say("admin commands can't be toggled!").await?;
+        return Ok(())
+    }
+
+    let scope = Scope::Guild(guild_id);
+    let storage = &context.data().storage;
+    let mut rules = storage.get(scope, &RULES).await?.unwrap_or_default();
+    rules.set(channel_id, &name, enabled);
+
+    let place = match channel_id {
+        Some(channel_id) => format!("in <#{channel_id}>"),
+        None => "in this guild".to_owned(),
+    };
+    let response = match enabled {
+        Some(true) => format!("enabling `{name}` {place}..."),
+        Some(false) => format!("disabling `{name}` {place}..."),
+        None => format!("resetting `{name}` {place}..."),
+    };
+    context.say(response).await?;
+
+    match rules.guild.is_empty() && rules.channels.is_empty() {
+        true => storage.remove(scope, &RULES).await?,
+        false => storage.set(scope, &RULES, &rules).await?,
+    };
+
+    Ok(())
+}
+
+/// Effective state of every command in the current channel.
+async fn list_toggles(context: Context<'_>) -> Result<()> {
+    let scope = Scope::Guild(context.guild_id().unwrap());
+    let rules = context.data().storage.get(scope, &RULES).await?.unwrap_or_default();
+    let channel_id = context.channel_id();
+
+    let lines = command_names(context)
+        .iter()
+        .map(|name| describe_toggle(&rules, channel_id, name))
+        .collect::<Vec<String>>();
+    let pages = lines
+        .chunks(TOGGLES_PER_PAGE)
+        .map(|lines| format!("commands in <#{channel_id}>:\n{}", lines.join("\n")))
+        .collect::<Vec<String>>();
+    helper::paginate(context, &pages).await?;
+
+    Ok(())
+}
+
+fn describe_toggle(rules: &Rules, channel_id: ChannelId, name: &str) -> String {
+    let (enabled, source) = rules.resolve(channel_id, name);
+    let state = match enabled {
+        true => "enabled",
+        false => "disabled",
+    };
+
+    match source {
+        Source::Channel(rule) => format!("`{name}` {state} (`{rule}` in this channel)"),
+        Source::Guild(rule) => format!("`{name}` {state} (`{rule}` in this guild)"),
+        Source::Default => format!("`{name}` {state}"),
+    }
+}
+
+/// Qualified names of every command and group, depth first.
+fn command_names(context: Context<'_>) -> Vec<String> {
+    fn walk(commands: &[poise::Command<Data, Error>], names: &mut Vec<String>) {
+        for command in commands {
+            names.push(command.qualified_name.clone());
+            walk(&command.subcommands, names);
+        }
+    }
+
+    let mut names = Vec::new();
+    walk(&context.framework().options().commands, &mut names);
+
+    names
+}
+
+async fn list_prefixes(context: Context<'_>) -> Result<()> {
+    let scope = Scope::Guild(context.guild_id().unwrap());
+    let config = context.data().config();
+
+    let response = match context.data().storage.get(scope, &PREFIXES).await? {
+        Some(prefixes) => format!("prefixes for this guild: {}", quote(&prefixes)),
+        None => format!("default prefixes: {}", quote(&config.core.prefixes)),
+    };
+    let response = if config.core.mention {
+        format!("{response}\nmentioning the bot works as a prefix too!")
+    } else {
+        response
+    };
+    context.say(response).await?;
+
+    Ok(())
+}
+
+/// A channel mention like `<#123>` or a bare id.
+fn parse_channel(word: &str) -> Option<ChannelId> {
+    serenity::parse_channel(word)
+        .or_else(|| word.parse().ok())
+        .map(ChannelId)
+}
+
+fn quote(prefixes: &[String]) -> String {
+    prefixes
+        .iter()
+        .map(|prefix| format!("`{prefix}`"))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
@@ -0,0 +1,47 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use poise::serenity_prelude::GuildId;
+use crate::admin::{PREFIXES, RULES};
+use crate::base::Data;
+use crate::core;
+use crate::helper::{Context, Result};
+use crate::storage::Scope;
+
+/// Refuses commands disabled for the current guild or channel, the `admin`
+/// group itself can never be disabled.
+pub async fn command_check(context: Context<'_>) -> Result<bool> {
+    match allows(context, &context.command().qualified_name).await? {
+        true => Ok(true),
+        false => core::deny(context, "this command is disabled here!").await,
+    }
+}
+
+/// Whether `name` is enabled where `context` was invoked.
+pub async fn allows(context: Context<'_>, name: &str) -> Result<bool> {
+    let Some(guild_id) = context.guild_id() else {
+        return Ok(true)
+    };
+
+    let Some(rules) = context.data().storage.get(Scope::Guild(guild_id), &RULES).await? else {
+        return Ok(true)
+    };
+
+    Ok(rules.allows(context.channel_id(), name))
+}
+
+/// Prefixes set for `guild_id`, replacing the configured ones.
+pub async fn prefixes(data: &Data, guild_id: GuildId) -> Result<Option<Vec<String>>> {
+    data.storage.get(Scope::Guild(guild_id), &PREFIXES).await
+}
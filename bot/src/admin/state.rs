@@ -0,0 +1,80 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use poise::serenity_prelude::ChannelId;
+use serde::{Deserialize, Serialize};
+use crate::storage::Key;
+
+pub const PREFIXES: Key<Vec<String>> = Key::cached("admin.prefixes");
+pub const RULES: Key<Rules> = Key::cached("admin.rules");
+
+/// Per-guild command toggles, keyed by qualified command or group name.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Rules {
+    pub guild: BTreeMap<String, bool>,
+    pub channels: BTreeMap<u64, BTreeMap<String, bool>>,
+}
+
+/// Where the effective state of a command comes from.
+pub enum Source {
+    Channel(String),
+    Guild(String),
+    Default,
+}
+
+impl Rules {
+    /// Channel rules win over guild rules, and within each the rule for the
+    /// longest matching name wins, so `owner presence` overrides `owner`.
+    pub fn resolve(&self, channel_id: ChannelId, name: &str) -> (bool, Source) {
+        if let Some((rule, enabled)) = self.channels.get(&channel_id.0).and_then(|rules| matching(rules, name)) {
+            return (enabled, Source::Channel(rule))
+        }
+
+        if let Some((rule, enabled)) = matching(&self.guild, name) {
+            return (enabled, Source::Guild(rule))
+        }
+
+        (true, Source::Default)
+    }
+
+    /// Whether `name` may run in `channel_id`, the `admin` group itself can
+    /// never be disabled.
+    pub fn allows(&self, channel_id: ChannelId, name: &str) -> bool {
+        name == "admin" || name.starts_with("admin ") || self.resolve(channel_id, name).0
+    }
+
+    pub fn set(&mut self, channel_id: Option<ChannelId>, name: &str, enabled: Option<bool>) {
+        let rules = match channel_id {
+            Some(channel_id) => self.channels.entry(channel_id.0).or_default(),
+            None => &mut self.guild,
+        };
+
+        match enabled {
+            Some(enabled) => rules.insert(name.to_owned(), enabled),
+            None => rules.remove(name),
+        };
+
+        self.channels.retain(|_, rules| !rules.is_empty());
+    }
+}
+
+fn matching(rules: &BTreeMap<String, bool>, name: &str) -> Option<(String, bool)> {
+    rules
+        .iter()
+        .filter(|(rule, _)| name == rule.as_str() || name.starts_with(&format!("{rule} ")))
+        .max_by_key(|(rule, _)| rule.len())
+        .map(|(rule, enabled)| (rule.clone(), *enabled))
+}
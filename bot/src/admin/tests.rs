@@ -0,0 +1,50 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use hyper::Method;
+use poise::serenity_prelude::GuildId;
+use crate::admin::RULES;
+use crate::mock::{Harness, CHANNEL, GUILD, OWNER};
+use crate::storage::Scope;
+
+#[tokio::test]
+async fn toggles_take_the_command_before_the_channel() {
+    let harness = Harness::start().await;
+
+    let reply = harness.command("!admin command disable owner status").await;
+    assert_eq!(reply, "disabling `owner status` in this guild...");
+    let reply = harness.command("!admin command enable owner status <#999>").await;
+    assert_eq!(reply, "there is no channel <#999> in this guild!");
+    let reply = harness.command("!admin command reset owner status 999").await;
+    assert_eq!(reply, "there is no channel <#999> in this guild!");
+
+    let rules = harness.data().await.storage.get(Scope::Guild(GuildId(GUILD)), &RULES).await.unwrap().unwrap();
+    assert_eq!(rules.guild.into_iter().collect::<Vec<(String, bool)>>(), [("owner status".to_owned(), false)]);
+    assert!(rules.channels.is_empty());
+}
+
+#[tokio::test]
+async fn help_leaves_out_disabled_commands() {
+    let harness = Harness::start().await;
+
+    harness.command("!admin command disable owner").await;
+    harness.command("!admin command enable owner shards").await;
+
+    assert_eq!(harness.command("!help owner").await, "no command named `owner` available here!");
+    assert_eq!(harness.command("!help owner status").await, "no command named `owner status` available here!");
+    harness.send(OWNER, "!help owner shards").await;
+    let call = harness.rest.wait(Method::POST, &format!("/channels/{CHANNEL}/messages")).await;
+    let description = call.body["embeds"][0]["description"].as_str().unwrap_or_default();
+    assert!(description.starts_with("`!owner shards"), "{description}");
+}
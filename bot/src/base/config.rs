@@ -0,0 +1,289 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt::{self, Display, Formatter};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use toml::Value;
+use toml::value::Table;
+use crate::base::Config;
+use crate::helper::Result;
+
+const DEFAULT_PATH: &str = "bot/config.toml";
+const PATH_VARIABLE: &str = "KANAE_CONFIG";
+const OVERRIDE_PREFIX: &str = "KANAE__";
+const OVERRIDE_SEPARATOR: &str = "__";
+
+/// Where the config file layer is read from.
+///
+/// An explicit path (`--config PATH`, `-c PATH` or `KANAE_CONFIG`) must exist,
+/// while the default `bot/config.toml` is skipped when missing so that a
+/// deployment can be configured purely through the environment.
+#[derive(Clone)]
+pub struct Source {
+    pub path: PathBuf,
+    pub explicit: bool,
+}
+
+impl Source {
+    pub fn from_env() -> Result<Self> {
+        let variable = match std::env::var_os(PATH_VARIABLE) {
+            Some(path) => match path.into_string() {
+                Ok(path) => Some(path),
+                Err(_) => return Err(format!("environment variable {PATH_VARIABLE} is not valid unicode").into()),
+            },
+            None => None,
+        };
+
+        Source::resolve(std::env::args().skip(1), variable)
+    }
+
+    /// The path given by `arguments`, else by the `KANAE_CONFIG` `variable`,
+    /// else the default one.
+    pub fn resolve(mut arguments: impl Iterator<Item = String>, variable: Option<String>) -> Result<Self> {
+        let mut path = None;
+
+        while let Some(argument) = arguments.next() {
+            let value = match argument.strip_prefix("--config=") {
+                Some(value) => Some(value.to_owned()),
+                None if argument == "--config" || argument == "-c" => arguments.next(),
+                None => continue,
+            };
+
+            match value {
+                Some(value) if !value.is_empty() && !value.starts_with('-') => path = Some(value),
+                _ => return Err(format!("`{}` needs a path", argument.trim_end_matches('=')).into()),
+            }
+        }
+
+        let source = match path.or(variable) {
+            Some(path) => Source { path: path.into(), explicit: true },
+            None => Source { path: DEFAULT_PATH.into(), explicit: false },
+        };
+
+        Ok(source)
+    }
+}
+
+/// A key of the merged config that its type refused, by dotted path.
+#[derive(Debug)]
+pub struct InvalidKey {
+    pub key: String,
+    pub reason: String,
+}
+
+impl Display for InvalidKey {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        write!(formatter, "invalid key `{}`: {}", self.key, self.reason)
+    }
+}
+
+impl std::error::Error for InvalidKey {}
+
+#[derive(Clone)]
+enum Layer {
+    Default,
+    File(PathBuf),
+    Environment(String),
+}
+
+impl Display for Layer {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Layer::Default => write!(formatter, "defaults"),
+            Layer::File(path) => write!(formatter, "config file {}", path.display()),
+            Layer::Environment(name) => write!(formatter, "environment variable {name}"),
+        }
+    }
+}
+
+/// Merges defaults, the config file and `KANAE__SECTION__KEY` overrides into
+/// a validated [`Config`].
+pub fn config(source: &Source) -> Result<Config> {
+    load(source, std::env::vars_os())
+}
+
+/// Same as [`config`] with the environment given as `variables`.
+pub fn load(source: &Source, variables: impl IntoIterator<Item = (OsString, OsString)>) -> Result<Config> {
+    let mut origins = HashMap::new();
+
+    let mut merged = Table::new();
+    let Value::Table(defaults) = Value::try_from(Config::default())? else { unreachable!() };
+    merge(&mut merged, defaults, &mut Vec::new(), &Layer::Default, &mut origins);
+
+    if let Some(file) = file(source)? {
+        let layer = Layer::File(source.path.clone());
+        merge(&mut merged, file, &mut Vec::new(), &layer, &mut origins);
+    }
+
+    let mut overrides = Vec::new();
+    for (name, value) in variables {
+        if !name.to_string_lossy().starts_with(OVERRIDE_PREFIX) {
+            continue
+        }
+
+        match (name.into_string(), value.into_string()) {
+            (Ok(name), Ok(value)) => overrides.push((name, value)),
+            (Ok(name), Err(_)) => return Err(format!("environment variable {name} is not valid unicode").into()),
+            (Err(name), _) => {
+                let name = name.to_string_lossy();
+                return Err(format!("environment variable {name} is not valid unicode").into())
+            }
+        }
+    }
+    overrides.sort();
+
+    let mut texts = HashMap::new();
+    for (name, value) in overrides {
+        environment(&mut merged, &name, value, &mut origins, &mut texts)?;
+    }
+
+    // an override that parsed as a number or boolean may be meant as text,
+    // like a presence named `123`, so it is retried verbatim where refused
+    let mut refused = HashMap::new();
+    loop {
+        let error = match parse(&merged) {
+            Ok(config) => return Ok(config),
+            Err(error) => error,
+        };
+
+        let Some(InvalidKey { key, reason }) = error.downcast_ref::<InvalidKey>() else {
+            return Err(error)
+        };
+
+        if let Some((keys, text)) = texts.remove(key) {
+            refused.insert(key.clone(), reason.clone());
+            parent(&mut merged, &keys, key)?.insert(keys[keys.len() - 1].clone(), Value::String(text));
+            continue
+        }
+
+        let reason = refused.get(key).unwrap_or(reason);
+        let layer = origins.get(key).map_or_else(|| "merged config".to_owned(), Layer::to_string);
+        return Err(format!("invalid key `{key}` from {layer}: {reason}").into())
+    }
+}
+
+fn parse(merged: &Table) -> Result<Config> {
+    let config: Config = serde_path_to_error::deserialize(Value::Table(merged.clone()))
+        .map_err(|error| InvalidKey { key: error.path().to_string(), reason: error.inner().to_string() })?;
+    config.validate()?;
+
+    Ok(config)
+}
+
+fn file(source: &Source) -> Result<Option<Table>> {
+    let content = match std::fs::read_to_string(&source.path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == ErrorKind::NotFound && !source.explicit => return Ok(None),
+        Err(error) => {
+            let path = source.path.display();
+            return Err(format!("error reading config file {path}: {error}").into())
+        }
+    };
+
+    let table = toml::from_str(&content).map_err(|error| {
+        let path = source.path.display();
+        format!("error parsing config file {path}: {error}")
+    })?;
+
+    Ok(Some(table))
+}
+
+/// Applies the override `name`, keeping the `raw` text of values that did
+/// not parse as a string in `texts` by dotted key.
+fn environment(
+    merged: &mut Table,
+    name: &str,
+    raw: String,
+    origins: &mut HashMap<String, Layer>,
+    texts: &mut HashMap<String, (Vec<String>, String)>,
+) -> Result<()> {
+    let keys = name[OVERRIDE_PREFIX.len()..]
+        .split(OVERRIDE_SEPARATOR)
+        .map(str::to_lowercase)
+        .collect::<Vec<String>>();
+
+    if keys.iter().any(String::is_empty) {
+        return Err(format!("error parsing environment variable {name}: empty key segment").into())
+    }
+
+    let path = keys.join(".");
+    let key = keys[keys.len() - 1].clone();
+    let table = parent(merged, &keys, name)?;
+
+    // strings stay verbatim so tokens and names never get reinterpreted as
+    // numbers or booleans, anything else is parsed as a toml value
+    let parsed = match table.get(&key) {
+        Some(Value::String(_)) => None,
+        _ => toml::from_str::<Table>(&format!("value = {raw}"))
+            .ok()
+            .and_then(|mut parsed| parsed.remove("value")),
+    };
+
+    match parsed.as_ref().is_some_and(|value| !value.is_str()) {
+        true => texts.insert(path.clone(), (keys.clone(), raw.clone())),
+        false => texts.remove(&path),
+    };
+
+    origins.insert(path, Layer::Environment(name.to_owned()));
+    table.insert(key, parsed.unwrap_or(Value::String(raw)));
+
+    Ok(())
+}
+
+/// The table holding the last of `keys`, created where missing. `name` is
+/// the setting being applied, for the error.
+fn parent<'a>(merged: &'a mut Table, keys: &[String], name: &str) -> Result<&'a mut Table> {
+    let mut table = merged;
+
+    for (index, parent) in keys[..keys.len() - 1].iter().enumerate() {
+        let entry = table
+            .entry(parent.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+
+        let Value::Table(inner) = entry else {
+            let path = keys[..=index].join(".");
+            return Err(format!("error applying environment variable {name}: `{path}` is not a table").into())
+        };
+        table = inner;
+    }
+
+    Ok(table)
+}
+
+fn merge(
+    target: &mut Table,
+    source: Table,
+    prefix: &mut Vec<String>,
+    layer: &Layer,
+    origins: &mut HashMap<String, Layer>,
+) {
+    for (key, value) in source {
+        prefix.push(key.clone());
+
+        match (target.get_mut(&key), value) {
+            (Some(Value::Table(existing)), Value::Table(value)) => {
+                merge(existing, value, prefix, layer, origins);
+            }
+            (_, value) => {
+                origins.insert(prefix.join("."), layer.clone());
+                target.insert(key, value);
+            }
+        }
+
+        prefix.pop();
+    }
+}
@@ -12,6 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod config;
+mod module;
+mod reload;
+mod shutdown;
 mod state;
+#[cfg(test)]
+mod tests;
 
-pub use state::{config, data, Config, Data};
+pub use config::{config, InvalidKey, Source};
+pub use module::{registry, Module, Registry};
+pub use reload::Reloader;
+pub use shutdown::{Guard, Shutdown};
+pub use state::{data, Config, Data, Shared};
@@ -0,0 +1,212 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use poise::{Command, Event, Framework, FrameworkContext};
+use poise::serenity_prelude::{self as serenity, Ready};
+use toml::Value;
+use crate::{admin, help, owner};
+use crate::base::{Config, Data};
+use crate::helper::{Context, Error, Result};
+
+/// Lets [`Registry::get`] find a module by its type, implemented for every
+/// module through the blanket impl.
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A feature module plugging its commands and hooks into the bot, keeping
+/// whatever state it needs on itself.
+///
+/// Every hook but [`Module::commands`] defaults to doing nothing.
+#[poise::async_trait]
+pub trait Module: AsAny + Send + Sync {
+    /// Identifies the module in logs, as its key under `[modules]` and as
+    /// the name of its own config section.
+    fn name(&self) -> &'static str;
+
+    /// Defaults of the module's own section, read back through
+    /// [`Config::section`], none for modules without settings.
+    fn config(&self) -> Option<Value> {
+        None
+    }
+
+    /// Checks the module's own section of the config, only while enabled.
+    fn validate(&self, _config: &Config) -> Result<()> {
+        Ok(())
+    }
+
+    fn commands(&self) -> Vec<Command<Data, Error>>;
+
+    /// Runs before every command, refusing it by returning `false` after
+    /// telling the user why.
+    async fn command_check(&self, _context: Context<'_>) -> Result<bool> {
+        Ok(true)
+    }
+
+    /// Builds the module state once the first `Ready` arrives.
+    async fn init(
+        &self,
+        _context: &serenity::Context,
+        _ready: &Ready,
+        _framework: &Framework<Data, Error>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    async fn event(
+        &self,
+        _context: &serenity::Context,
+        _event: &Event<'_>,
+        _framework: FrameworkContext<'_, Data, Error>,
+        _data: &Data,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs once running commands drained, before storage is flushed.
+    async fn shutdown(&self, _data: &Data) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Every module built into the bot, in the order their hooks run.
+pub fn registry() -> Registry {
+    Registry {
+        modules: vec![
+            Box::new(admin::Module),
+            Box::new(help::Module),
+            Box::new(owner::Module::default()),
+        ],
+    }
+}
+
+pub struct Registry {
+    modules: Vec<Box<dyn Module>>,
+}
+
+impl Registry {
+    /// Keeps the modules not turned off under `[modules]`.
+    pub fn enabled(self, config: &Config) -> Self {
+        let modules = self.modules
+            .into_iter()
+            .filter(|module| config.enabled(module.name()))
+            .collect();
+
+        Registry { modules }
+    }
+
+    pub fn validate(&self, config: &Config) -> Result<()> {
+        for name in config.modules.keys() {
+            if !self.modules.iter().any(|module| module.name() == name) {
+                return Err(format!("`modules.{name}` is not a known module").into())
+            }
+        }
+
+        for name in config.sections.keys() {
+            if !self.modules.iter().any(|module| module.name() == name && module.config().is_some()) {
+                return Err(format!("`{name}` is not a known config section").into())
+            }
+        }
+
+        for module in &self.modules {
+            if config.enabled(module.name()) {
+                module.validate(config)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The module of type `M`, none while it is disabled.
+    pub fn get<M: Module + 'static>(&self) -> Option<&M> {
+        self.modules.iter().find_map(|module| module.as_ref().as_any().downcast_ref())
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.modules.iter().map(|module| module.name()).collect()
+    }
+
+    /// Default sections of the modules that have one, by module name.
+    pub fn sections(&self) -> BTreeMap<String, Value> {
+        self.modules
+            .iter()
+            .filter_map(|module| Some((module.name().to_owned(), module.config()?)))
+            .collect()
+    }
+
+    pub fn commands(&self) -> Vec<Command<Data, Error>> {
+        self.modules.iter().flat_map(|module| module.commands()).collect()
+    }
+
+    pub async fn init(
+        &self,
+        context: &serenity::Context,
+        ready: &Ready,
+        framework: &Framework<Data, Error>,
+    ) -> Result<()> {
+        for module in &self.modules {
+            module.init(context, ready, framework).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Lets the command run only when every module agrees.
+    pub async fn command_check(&self, context: Context<'_>) -> Result<bool> {
+        for module in &self.modules {
+            if !module.command_check(context).await? {
+                return Ok(false)
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Dispatches `event` to every module, a failing module is logged without
+    /// keeping the event from the ones after it.
+    pub async fn event(
+        &self,
+        context: &serenity::Context,
+        event: &Event<'_>,
+        framework: FrameworkContext<'_, Data, Error>,
+        data: &Data,
+    ) {
+        for module in &self.modules {
+            if let Err(error) = module.event(context, event, framework, data).await {
+                let message = "error dispatching module handler";
+                let module = module.name();
+                let event = event.name();
+                tracing::error!(message, module, event, error);
+            }
+        }
+    }
+
+    pub async fn shutdown(&self, data: &Data) {
+        for module in &self.modules {
+            if let Err(error) = module.shutdown(data).await {
+                let message = "error shutting down module";
+                let module = module.name();
+                tracing::error!(message, module, error);
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::SystemTime;
+use arc_swap::ArcSwap;
+use tokio::time::Duration;
+use crate::base::{self, Config, Source};
+use crate::helper::Result;
+use crate::log::Logger;
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Owns the live [`Config`] and swaps it in place when the file changes.
+#[derive(Clone)]
+pub struct Reloader {
+    source: Source,
+    config: Arc<ArcSwap<Config>>,
+    logger: Arc<Logger>,
+}
+
+impl Reloader {
+    pub fn new(source: Source, config: Config, logger: Arc<Logger>) -> Self {
+        Reloader {
+            source,
+            config: Arc::new(ArcSwap::from_pointee(config)),
+            logger,
+        }
+    }
+
+    pub fn config(&self) -> Arc<Config> {
+        self.config.load_full()
+    }
+
+    /// Re-reads every layer and swaps the result in, keeping the current
+    /// config untouched when loading or validation fails.
+    pub fn reload(&self) -> Result<()> {
+        let mut config = base::config(&self.source)?;
+        let current = self.config.load();
+
+        // the gateway connection is only established once, so connection
+        // settings keep their startup value until the next restart
+        if config.core.token != current.core.token {
+            tracing::warn!("ignoring change of `core.token` until restart");
+            config.core.token = current.core.token.clone();
+        }
+
+        if config.core.shards != current.core.shards {
+            tracing::warn!("ignoring change of `core.shards` until restart");
+            config.core.shards = current.core.shards;
+        }
+
+        if config.storage.engine != current.storage.engine || config.storage.path != current.storage.path {
+            tracing::warn!("ignoring change of `storage` until restart");
+            config.storage.engine = current.storage.engine;
+            config.storage.path = current.storage.path.clone();
+        }
+
+        if config.modules != current.modules {
+            tracing::warn!("ignoring change of `modules` until restart");
+            config.modules = current.modules.clone();
+        }
+
+        if config.server.address != current.server.address {
+            tracing::warn!("ignoring change of `server.address` until restart");
+            config.server.address = current.server.address;
+        }
+
+        // outputs are wired once at startup, only the filter can change live
+        let log = &config.log;
+        if log.format != current.log.format || log.ansi != current.log.ansi || log.file != current.log.file {
+            tracing::warn!("ignoring change of `log` outputs until restart");
+            config.log.format = current.log.format;
+            config.log.ansi = current.log.ansi;
+            config.log.file = current.log.file.clone();
+        }
+
+        self.logger.reload(&config.log)?;
+        self.config.store(Arc::new(config));
+        tracing::info!("config reloaded");
+
+        Ok(())
+    }
+
+    /// Polls the config file and reloads whenever its modification time moves.
+    pub async fn watch(self) {
+        let mut modified = self.modified();
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let current = self.modified();
+            if current == modified {
+                continue
+            }
+            modified = current;
+
+            if let Err(error) = self.reload() {
+                let message = "error reloading config, keeping previous one";
+                tracing::error!(message, error);
+            }
+        }
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(&self.source.path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Exit code after `owner restart`, so that a supervisor can tell a requested
+/// restart apart from a plain shutdown.
+const RESTART_CODE: i32 = 75;
+
+/// Coordinates a graceful shutdown across commands and background tasks.
+///
+/// Triggering it cancels the shared token, which refuses new commands and
+/// stops tasks started through [`Shutdown::spawn`], then [`Shutdown::drain`]
+/// waits for whatever still holds a [`Guard`].
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    token: CancellationToken,
+    restart: Arc<AtomicBool>,
+    active: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+}
+
+/// Marks a unit of work as in flight until dropped.
+pub struct Guard {
+    token: CancellationToken,
+    active: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+}
+
+impl Shutdown {
+    pub fn trigger(&self) {
+        self.token.cancel();
+    }
+
+    /// Triggers shutdown, exiting with [`RESTART_CODE`] afterwards.
+    pub fn restart(&self) {
+        self.restart.store(true, Ordering::SeqCst);
+        self.trigger();
+    }
+
+    pub fn code(&self) -> i32 {
+        match self.restart.load(Ordering::SeqCst) {
+            true => RESTART_CODE,
+            false => 0,
+        }
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub async fn triggered(&self) {
+        self.token.cancelled().await
+    }
+
+    /// Cancelled once shutdown is triggered, for work that waits on users or
+    /// the network and should give up rather than hold up the drain.
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Commands and tasks currently holding a [`Guard`].
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    pub fn enter(&self) -> Guard {
+        self.active.fetch_add(1, Ordering::SeqCst);
+
+        Guard { token: self.token.clone(), active: self.active.clone(), idle: self.idle.clone() }
+    }
+
+    /// Runs `future` until it completes or shutdown is triggered.
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let guard = self.enter();
+
+        tokio::spawn(async move {
+            tokio::select!{
+                _ = future => (),
+                _ = guard.cancelled() => (),
+            };
+        })
+    }
+
+    /// Waits up to `grace` for every [`Guard`] to drop, returning how many are
+    /// still alive afterwards.
+    pub async fn drain(&self, grace: Duration) -> usize {
+        let idle = async {
+            loop {
+                let notified = self.idle.notified();
+                if self.active.load(Ordering::SeqCst) == 0 {
+                    break
+                }
+                notified.await;
+            }
+        };
+        let _ = tokio::time::timeout(grace, idle).await;
+
+        self.active.load(Ordering::SeqCst)
+    }
+}
+
+impl Guard {
+    pub async fn cancelled(&self) {
+        self.token.cancelled().await
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if self.active.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+}
@@ -12,42 +12,127 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
 use poise::Framework;
 use poise::serenity_prelude::{Context, Ready};
-use serde::Deserialize;
-use crate::{core, owner};
-use crate::helper::{ArcMut, Error, Result};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use toml::Value;
+use toml::value::Table;
+use crate::{cooldown, core, log, server, storage};
+use crate::base::{self, InvalidKey, Registry, Reloader, Shutdown};
+use crate::helper::{Error, Result};
+use crate::metrics::Metrics;
+use crate::server::Health;
+use crate::storage::Storage;
 
 pub async fn data(
     context: &Context,
     ready: &Ready,
     framework: &Framework<Data, Error>,
+    shared: Shared,
 ) -> Result<Data> {
+    let Shared { reloader, metrics, health, storage, shutdown, modules } = shared;
+    modules.init(context, ready, framework).await?;
+
     let data = Data {
-        owner: owner::data(context, ready, framework).await?,
+        started: Instant::now(),
+        reloader,
+        metrics,
+        health,
+        storage,
+        shutdown,
+        cooldowns: cooldown::Limiter::default(),
+        modules,
     };
 
     Ok(data)
 }
 
-pub fn config(path: &str) -> Result<Config> {
-    let content = std::fs::read_to_string(path)?;
-    let config = toml::from_str(&content)?;
-
-    Ok(config)
+/// Handles built in `run()` before connecting, moved into [`Data`] once the
+/// framework is set up.
+#[derive(Clone)]
+pub struct Shared {
+    pub reloader: Reloader,
+    pub metrics: Arc<Metrics>,
+    pub health: Arc<Health>,
+    pub storage: Storage,
+    pub shutdown: Shutdown,
+    pub modules: Arc<Registry>,
 }
 
 pub struct Data {
-    pub owner: ArcMut<owner::Data>,
+    pub started: Instant,
+    pub reloader: Reloader,
+    pub metrics: Arc<Metrics>,
+    pub health: Arc<Health>,
+    pub storage: Storage,
+    pub shutdown: Shutdown,
+    pub cooldowns: cooldown::Limiter,
+    /// The enabled modules, fixed until restart.
+    pub modules: Arc<Registry>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
 pub struct Config {
     pub core: core::Config,
+    pub cooldown: cooldown::Config,
+    pub log: log::Config,
+    /// Modules set to `false` are left out entirely, the others are enabled.
+    pub modules: BTreeMap<String, bool>,
+    pub server: server::Config,
+    pub storage: storage::Config,
+    /// Sections of the modules, by module name, each read by its own module.
+    #[serde(flatten)]
+    pub sections: BTreeMap<String, Value>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            core: core::Config::default(),
+            cooldown: cooldown::Config::default(),
+            log: log::Config::default(),
+            modules: BTreeMap::new(),
+            server: server::Config::default(),
+            storage: storage::Config::default(),
+            sections: base::registry().sections(),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<()> {
+        self.core.validate()?;
+        self.cooldown.validate()?;
+        self.log.validate()?;
+        self.storage.validate()?;
+        base::registry().validate(self)?;
+
+        Ok(())
+    }
+
+    pub fn enabled(&self, module: &str) -> bool {
+        self.modules.get(module).copied().unwrap_or(true)
+    }
+
+    /// Reads the section of `module`, taking the defaults of its type when
+    /// the section is missing.
+    pub fn section<T: DeserializeOwned>(&self, module: &str) -> Result<T> {
+        let section = self.sections.get(module).cloned().unwrap_or_else(|| Value::Table(Table::new()));
+
+        serde_path_to_error::deserialize(section).map_err(|error| {
+            let key = format!("{module}.{}", error.path());
+            InvalidKey { key, reason: error.inner().to_string() }.into()
+        })
+    }
 }
 
 impl Data {
-    pub fn owner(&self) -> ArcMut<owner::Data> {
-        self.owner.clone()
+    pub fn config(&self) -> Arc<Config> {
+        self.reloader.config()
     }
 }
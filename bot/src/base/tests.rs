@@ -0,0 +1,169 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use poise::serenity_prelude::{GuildId, UserId};
+use crate::base::{self, Source};
+use crate::base::config;
+use crate::{admin, owner};
+
+/// Loads `content` as an explicit config file with `variables` as the environment.
+fn layered(name: &str, content: &str, variables: &[(&str, &str)]) -> Result<base::Config, String> {
+    let path = std::env::temp_dir().join(format!("bot-{}-{name}.toml", std::process::id()));
+    std::fs::write(&path, content).unwrap();
+
+    let variables = variables.iter().map(|(name, value)| (OsString::from(name), OsString::from(value)));
+    let config = config::load(&Source { path: PathBuf::from(&path), explicit: true }, variables);
+    std::fs::remove_file(&path).unwrap();
+
+    config.map_err(|error| error.to_string())
+}
+
+/// Loads `content` as an explicit config file along with a token.
+fn load(name: &str, content: &str) -> Result<base::Config, String> {
+    layered(name, &format!("[core]\ntoken = \"mock\"\n\n{content}"), &[])
+}
+
+fn resolve(arguments: &[&str], variable: Option<&str>) -> Result<(PathBuf, bool), String> {
+    let arguments = arguments.iter().map(|argument| argument.to_string());
+
+    Source::resolve(arguments, variable.map(str::to_owned))
+        .map(|source| (source.path, source.explicit))
+        .map_err(|error| error.to_string())
+}
+
+#[test]
+fn module_sections_are_read_by_their_module() {
+    let config = load("section", "[owner]\nguild = 1\n\n[owner.announce]\nbefore = [60]\n").unwrap();
+
+    let owner = owner::Config::of(&config);
+    assert_eq!(owner.guild, Some(GuildId(1)));
+    assert_eq!(owner.announce.before, [60]);
+}
+
+#[test]
+fn module_sections_default_when_missing() {
+    let config = load("missing", "").unwrap();
+
+    assert_eq!(owner::Config::of(&config).announce.before, [3600, 600, 60]);
+}
+
+#[test]
+fn invalid_module_sections_are_refused() {
+    let Err(error) = load("invalid", "[owner]\nguild = \"one\"\n") else {
+        panic!("config loaded")
+    };
+    assert!(error.starts_with("invalid key `owner.guild`"), "{error}");
+
+    let Err(error) = load("unknown", "[onwer]\nguild = 1\n") else {
+        panic!("config loaded")
+    };
+    assert_eq!(error, "`onwer` is not a known config section");
+}
+
+#[test]
+fn modules_are_found_by_type_while_enabled() {
+    let mut config = base::Config::default();
+    config.modules.insert("owner".to_owned(), false);
+
+    let modules = base::registry().enabled(&config);
+    assert!(modules.get::<admin::Module>().is_some());
+    assert!(modules.get::<owner::Module>().is_none());
+}
+
+#[test]
+fn layers_override_defaults_then_the_file() {
+    let file = "[core]\ntoken = \"mock\"\nprefixes = [\"?\"]\ngrace = 5\n";
+    let config = layered("layers", file, &[("KANAE__CORE__GRACE", "9"), ("OTHER__CORE__GRACE", "1")]).unwrap();
+
+    assert_eq!(config.core.prefixes, ["?"]);
+    assert_eq!(config.core.grace, 9);
+    assert!(config.core.mention);
+}
+
+#[test]
+fn overrides_parse_values_and_keep_text_as_is() {
+    let variables = [
+        ("KANAE__CORE__TOKEN", "123"),
+        ("KANAE__CORE__MENTION", "false"),
+        ("KANAE__CORE__OWNERS", "[1, 2]"),
+        ("KANAE__OWNER__PRESENCE__STATUS", "online"),
+        ("KANAE__OWNER__PRESENCE__NAME", "123"),
+    ];
+    let config = layered("overrides", "", &variables).unwrap();
+
+    assert_eq!(config.core.token, "123");
+    assert!(!config.core.mention);
+    assert_eq!(config.core.owners, [UserId(1), UserId(2)]);
+    let presence = owner::Config::of(&config).presence.unwrap();
+    assert_eq!(presence.describe(), "online 123");
+}
+
+#[test]
+fn errors_name_the_layer_of_the_key() {
+    let Err(error) = load("file", "[owner]\nguild = \"one\"\n") else {
+        panic!("config loaded")
+    };
+    assert!(error.starts_with("invalid key `owner.guild` from config file "), "{error}");
+
+    let file = "[core]\ntoken = \"mock\"\n";
+    let Err(error) = layered("environment", file, &[("KANAE__CORE__MENTION", "5")]) else {
+        panic!("config loaded")
+    };
+    let expected = "invalid key `core.mention` from environment variable KANAE__CORE__MENTION: invalid type: integer `5`";
+    assert!(error.starts_with(expected), "{error}");
+
+    let Err(error) = layered("section", file, &[("KANAE__OWNER__GUILD", "one")]) else {
+        panic!("config loaded")
+    };
+    assert!(error.starts_with("invalid key `owner.guild` from environment variable KANAE__OWNER__GUILD: "), "{error}");
+}
+
+#[cfg(unix)]
+#[test]
+fn overrides_must_be_unicode() {
+    use std::os::unix::ffi::OsStringExt;
+
+    let path = std::env::temp_dir().join("bot-missing.toml");
+    let variables = [(OsString::from("KANAE__CORE__TOKEN"), OsString::from_vec(vec![0xff]))];
+    let Err(error) = config::load(&Source { path, explicit: false }, variables) else {
+        panic!("config loaded")
+    };
+    assert_eq!(error.to_string(), "environment variable KANAE__CORE__TOKEN is not valid unicode");
+
+    // other variables are none of the config's business
+    let path = std::env::temp_dir().join("bot-missing.toml");
+    let variables = [
+        (OsString::from("KANAE__CORE__TOKEN"), OsString::from("mock")),
+        (OsString::from_vec(vec![0xff]), OsString::from_vec(vec![0xff])),
+    ];
+    assert!(config::load(&Source { path, explicit: false }, variables).is_ok());
+}
+
+#[test]
+fn config_path_comes_from_arguments_then_the_environment() {
+    assert_eq!(resolve(&[], None), Ok(("bot/config.toml".into(), false)));
+    assert_eq!(resolve(&[], Some("env.toml")), Ok(("env.toml".into(), true)));
+    assert_eq!(resolve(&["--config", "a.toml"], Some("env.toml")), Ok(("a.toml".into(), true)));
+    assert_eq!(resolve(&["-c", "a.toml"], None), Ok(("a.toml".into(), true)));
+    assert_eq!(resolve(&["--config=a.toml"], None), Ok(("a.toml".into(), true)));
+}
+
+#[test]
+fn config_flags_need_a_path() {
+    assert_eq!(resolve(&["--config"], Some("env.toml")), Err("`--config` needs a path".to_owned()));
+    assert_eq!(resolve(&["--config="], None), Err("`--config` needs a path".to_owned()));
+    assert_eq!(resolve(&["-c", "--verbose"], None), Err("`-c` needs a path".to_owned()));
+}
@@ -0,0 +1,155 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::time::Duration;
+use crate::cooldown::Config;
+use crate::core::{self, Denial};
+use crate::helper::{Context, Result};
+
+/// Pruned of idle buckets once it grows past this many.
+const MAX_BUCKETS: usize = 4096;
+
+type Bucket = (String, &'static str, u64);
+
+/// Sliding-window use counts per rule, bucket kind and bucket id.
+#[derive(Default)]
+pub struct Limiter {
+    buckets: Mutex<HashMap<Bucket, Uses>>,
+}
+
+/// Recent uses of a bucket along with the period they count towards, kept
+/// to tell when the bucket went idle.
+#[derive(Default)]
+struct Uses {
+    period: Duration,
+    times: VecDeque<Instant>,
+}
+
+impl Limiter {
+    /// How long until the most exhausted bucket frees up, none when a use
+    /// would fit into every one.
+    pub fn remaining(&self, buckets: &[(Bucket, Duration, usize)], now: Instant) -> Option<Duration> {
+        exhausted(&mut self.buckets.lock().unwrap(), buckets, now)
+    }
+
+    /// Records a use in every bucket, exhausted or not.
+    pub fn record(&self, buckets: &[(Bucket, Duration, usize)], now: Instant) {
+        insert(&mut self.buckets.lock().unwrap(), buckets, now);
+    }
+
+    /// Takes back the uses recorded at `at`.
+    pub fn release(&self, buckets: &[(Bucket, Duration, usize)], at: Instant) {
+        let mut entries = self.buckets.lock().unwrap();
+
+        for (bucket, _, _) in buckets {
+            let Some(uses) = entries.get_mut(bucket) else {
+                continue
+            };
+
+            if let Some(index) = uses.times.iter().rposition(|used| *used == at) {
+                uses.times.remove(index);
+            }
+        }
+    }
+}
+
+fn exhausted(
+    entries: &mut HashMap<Bucket, Uses>,
+    buckets: &[(Bucket, Duration, usize)],
+    now: Instant,
+) -> Option<Duration> {
+    buckets
+        .iter()
+        .filter_map(|(bucket, period, burst)| {
+            let uses = &mut entries.get_mut(bucket)?.times;
+            while uses.front().is_some_and(|used| now.duration_since(*used) >= *period) {
+                uses.pop_front();
+            }
+
+            (uses.len() >= *burst).then(|| *period - now.duration_since(uses[0]))
+        })
+        .max()
+}
+
+fn insert(entries: &mut HashMap<Bucket, Uses>, buckets: &[(Bucket, Duration, usize)], now: Instant) {
+    for (bucket, period, _) in buckets {
+        let uses = entries.entry(bucket.clone()).or_default();
+        uses.period = *period;
+        uses.times.push_back(now);
+    }
+
+    if entries.len() > MAX_BUCKETS {
+        entries.retain(|_, uses| uses.times.back().is_some_and(|used| now.duration_since(*used) < uses.period));
+    }
+}
+
+/// Refuses the invocation while any bucket of its rule is exhausted. The use
+/// itself is only recorded by [`record`] once the invocation starts.
+pub async fn command_check(context: Context<'_>) -> Result<bool> {
+    let data = context.data();
+    let config = data.config();
+
+    let Some(buckets) = buckets(context, &config.cooldown) else {
+        return Ok(true)
+    };
+
+    match data.cooldowns.remaining(&buckets, Instant::now()) {
+        Some(remaining) => core::refuse(context, Denial::Cooldown(remaining)).await,
+        None => Ok(true),
+    }
+}
+
+/// Counts an invocation that passed its checks as a use at `now`.
+pub fn record(context: Context<'_>, now: Instant) {
+    let data = context.data();
+
+    if let Some(buckets) = buckets(context, &data.config().cooldown) {
+        data.cooldowns.record(&buckets, now);
+    }
+}
+
+/// Takes back the use recorded at `at`, for an invocation whose arguments
+/// did not parse and which never ran.
+pub fn release(context: Context<'_>, at: Instant) {
+    let data = context.data();
+
+    if let Some(buckets) = buckets(context, &data.config().cooldown) {
+        data.cooldowns.release(&buckets, at);
+    }
+}
+
+fn buckets(context: Context<'_>, config: &Config) -> Option<Vec<(Bucket, Duration, usize)>> {
+    let (name, rule) = config.rule(&context.command().qualified_name)?;
+
+    let ids = [
+        ("user", rule.user, Some(context.author().id.0)),
+        ("channel", rule.channel, Some(context.channel_id().0)),
+        ("guild", rule.guild, context.guild_id().map(|guild_id| guild_id.0)),
+        ("global", rule.global, Some(0)),
+    ];
+
+    let buckets = ids
+        .into_iter()
+        .filter_map(|(kind, limit, id)| {
+            let limit = limit?;
+            let bucket = (name.to_owned(), kind, id?);
+            Some((bucket, Duration::from_secs(limit.period), limit.burst))
+        })
+        .collect();
+
+    Some(buckets)
+}
@@ -0,0 +1,93 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::helper::Result;
+
+/// Rules keyed by qualified command or group name, the longest matching name
+/// applies and its uses are shared by every command below it.
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub commands: BTreeMap<String, Rule>,
+}
+
+/// Independent buckets, a command runs only when none of them is exhausted.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Rule {
+    pub user: Option<Limit>,
+    pub channel: Option<Limit>,
+    pub guild: Option<Limit>,
+    pub global: Option<Limit>,
+}
+
+/// At most `burst` uses per `period` seconds.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct Limit {
+    pub period: u64,
+    #[serde(default = "default_burst")]
+    pub burst: usize,
+}
+
+fn default_burst() -> usize {
+    1
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<()> {
+        for (name, rule) in &self.commands {
+            let limits = [rule.user, rule.channel, rule.guild, rule.global];
+
+            if limits.iter().flatten().any(|limit| limit.period == 0 || limit.burst == 0) {
+                let message = format!("`cooldown.commands.\"{name}\"` limits need a period and burst of at least 1");
+                return Err(message.into())
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn rule(&self, name: &str) -> Option<(&str, &Rule)> {
+        self.commands
+            .iter()
+            .filter(|(rule, _)| name == rule.as_str() || name.starts_with(&format!("{rule} ")))
+            .max_by_key(|(rule, _)| rule.len())
+            .map(|(rule, limits)| (rule.as_str(), limits))
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let profile = Rule {
+            user: Some(Limit { period: 600, burst: 2 }),
+            ..Default::default()
+        };
+        let presence = Rule {
+            user: Some(Limit { period: 60, burst: 5 }),
+            ..Default::default()
+        };
+
+        let commands = [
+            ("owner avatar", profile.clone()),
+            ("owner banner", profile),
+            ("owner presence", presence),
+        ];
+
+        Config {
+            commands: commands.into_iter().map(|(name, rule)| (name.to_owned(), rule)).collect(),
+        }
+    }
+}
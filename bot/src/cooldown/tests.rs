@@ -0,0 +1,145 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Instant;
+use tokio::time::Duration;
+use crate::cooldown::{Config, Limiter};
+use crate::cooldown::state::{Limit, Rule};
+use crate::mock::Harness;
+
+fn user(id: u64, period: u64, burst: usize) -> ((String, &'static str, u64), Duration, usize) {
+    (("ping".to_owned(), "user", id), Duration::from_secs(period), burst)
+}
+
+/// Checks and records like a passing invocation would.
+fn acquire(limiter: &Limiter, buckets: &[((String, &'static str, u64), Duration, usize)], now: Instant) -> Option<Duration> {
+    let remaining = limiter.remaining(buckets, now);
+    if remaining.is_none() {
+        limiter.record(buckets, now);
+    }
+
+    remaining
+}
+
+#[test]
+fn acquire_allows_a_burst_then_reports_the_remaining_time() {
+    let limiter = Limiter::default();
+    let now = Instant::now();
+
+    assert_eq!(acquire(&limiter, &[user(1, 10, 2)], now), None);
+    assert_eq!(acquire(&limiter, &[user(1, 10, 2)], now + Duration::from_secs(1)), None);
+    assert_eq!(acquire(&limiter, &[user(1, 10, 2)], now + Duration::from_secs(4)), Some(Duration::from_secs(6)));
+
+    // other users have buckets of their own
+    assert_eq!(acquire(&limiter, &[user(2, 10, 2)], now + Duration::from_secs(4)), None);
+}
+
+#[test]
+fn acquire_slides_the_window() {
+    let limiter = Limiter::default();
+    let now = Instant::now();
+
+    acquire(&limiter, &[user(1, 10, 2)], now);
+    acquire(&limiter, &[user(1, 10, 2)], now + Duration::from_secs(5));
+
+    // only the first use left the window
+    assert_eq!(acquire(&limiter, &[user(1, 10, 2)], now + Duration::from_secs(10)), None);
+    let remaining = acquire(&limiter, &[user(1, 10, 2)], now + Duration::from_secs(12));
+    assert_eq!(remaining, Some(Duration::from_secs(3)));
+}
+
+#[test]
+fn acquire_records_nothing_while_any_bucket_is_exhausted() {
+    let limiter = Limiter::default();
+    let now = Instant::now();
+    let global = (("ping".to_owned(), "global", 0), Duration::from_secs(10), 2);
+
+    assert_eq!(acquire(&limiter, &[user(1, 10, 1), global.clone()], now), None);
+    assert!(acquire(&limiter, &[user(1, 10, 1), global.clone()], now).is_some());
+
+    // the refused use didn't count towards the global bucket
+    assert_eq!(acquire(&limiter, &[user(2, 10, 1), global.clone()], now), None);
+    assert!(acquire(&limiter, &[user(3, 10, 1), global], now).is_some());
+}
+
+#[test]
+fn pruning_keeps_buckets_within_their_own_period() {
+    let limiter = Limiter::default();
+    let now = Instant::now();
+    let later = now + Duration::from_secs(30);
+
+    acquire(&limiter, &[user(0, 60, 1)], now);
+    // enough short buckets to prune, all used after the long one
+    for id in 1..=4096 {
+        acquire(&limiter, &[user(id, 1, 1)], later);
+    }
+
+    let remaining = acquire(&limiter, &[user(0, 60, 1)], later);
+    assert_eq!(remaining, Some(Duration::from_secs(30)));
+}
+
+#[test]
+fn rule_prefers_the_longest_matching_name() {
+    let limit = Rule { user: Some(Limit { period: 1, burst: 1 }), ..Rule::default() };
+    let config = Config {
+        commands: [("owner", limit.clone()), ("owner presence", limit)]
+            .into_iter()
+            .map(|(name, rule)| (name.to_owned(), rule))
+            .collect(),
+    };
+
+    assert_eq!(config.rule("owner presence set").map(|(name, _)| name), Some("owner presence"));
+    assert_eq!(config.rule("owner presence").map(|(name, _)| name), Some("owner presence"));
+    assert_eq!(config.rule("owner status").map(|(name, _)| name), Some("owner"));
+    assert_eq!(config.rule("ownership").map(|(name, _)| name), None);
+    assert_eq!(config.rule("help").map(|(name, _)| name), None);
+}
+
+#[test]
+fn released_uses_free_their_slot() {
+    let limiter = Limiter::default();
+    let now = Instant::now();
+
+    limiter.record(&[user(1, 10, 1)], now);
+    assert!(limiter.remaining(&[user(1, 10, 1)], now).is_some());
+
+    limiter.release(&[user(1, 10, 1)], now);
+    assert_eq!(limiter.remaining(&[user(1, 10, 1)], now), None);
+}
+
+#[tokio::test]
+async fn subcommands_use_one_slot_per_invocation() {
+    let harness = Harness::start().await;
+
+    // `owner avatar` allows 2 uses per 600 seconds by default
+    assert_eq!(harness.command("!owner avatar set").await, "attachment or url is required!");
+    assert_eq!(harness.command("!owner avatar set").await, "attachment or url is required!");
+    assert_eq!(harness.command("!owner avatar").await, "this command is on cooldown, try again in 600s!");
+}
+
+#[tokio::test]
+async fn arguments_that_fail_to_parse_cost_nothing() {
+    let harness = Harness::with_config(|config| {
+        let rule = Rule { user: Some(Limit { period: 60, burst: 1 }), ..Default::default() };
+        config.cooldown.commands.insert("owner presence".to_owned(), rule);
+    })
+    .await;
+
+    for _ in 0..2 {
+        let reply = harness.command("!owner presence set sleepy").await;
+        assert!(reply.starts_with("couldn't parse `sleepy`"), "{reply}");
+    }
+    assert_eq!(harness.command("!owner presence set idle").await, "setting presence...");
+    assert_eq!(harness.command("!owner presence set idle").await, "this command is on cooldown, try again in 60s!");
+}
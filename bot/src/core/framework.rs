@@ -0,0 +1,77 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use poise::{Command, Framework, FrameworkOptions, PrefixFrameworkOptions};
+use poise::serenity_prelude::{self as serenity, ClientBuilder, GatewayIntents, UserId};
+use crate::base::{self, Data, Shared};
+use crate::core::{self, Shards};
+use crate::helper::Error;
+
+pub fn intents() -> GatewayIntents {
+    GatewayIntents::empty()
+        | GatewayIntents::GUILDS
+        | GatewayIntents::GUILD_MESSAGES
+        | GatewayIntents::MESSAGE_CONTENT
+}
+
+pub fn options(commands: Vec<Command<Data, Error>>, owners: HashSet<UserId>) -> FrameworkOptions<Data, Error> {
+    let prefix_options = PrefixFrameworkOptions {
+        prefix: None,
+        stripped_dynamic_prefix: Some(|c, m, d| Box::pin(core::prefix(c, m, d))),
+        mention_as_prefix: false,
+        ..Default::default()
+    };
+
+    FrameworkOptions {
+        commands,
+        owners,
+        on_error: |e| Box::pin(core::on_error(e)),
+        event_handler: |c, e, f, d| Box::pin(core::event_handler(c, e, f, d)),
+        pre_command: |c| Box::pin(core::pre_command(c)),
+        post_command: |c| Box::pin(core::post_command(c)),
+        command_check: Some(|c| Box::pin(core::command_check(c))),
+        prefix_options,
+        ..Default::default()
+    }
+}
+
+/// Builds the framework over `client`, which decides where HTTP requests and
+/// the gateway connection go, setting up [`Data`] on the first `Ready`.
+pub async fn framework(
+    client: ClientBuilder,
+    options: FrameworkOptions<Data, Error>,
+    shared: Shared,
+) -> serenity::Result<Arc<Framework<Data, Error>>> {
+    Framework::new(
+        client,
+        move |c, r, f| Box::pin(async move { base::data(c, r, f, shared).await }),
+        options,
+    ).await
+}
+
+/// Connects the shards configured for this process, returning once they all
+/// shut down.
+pub async fn start(framework: Arc<Framework<Data, Error>>, shards: Shards) -> serenity::Result<()> {
+    match (shards.total, shards.range) {
+        (None, _) => framework.start_autosharded().await,
+        (Some(total), None) => {
+            framework.start_with(|mut client| async move { client.start_shards(total).await }).await
+        }
+        (Some(total), Some(range)) => {
+            framework.start_with(|mut client| async move { client.start_shard_range(range, total).await }).await
+        }
+    }
+}
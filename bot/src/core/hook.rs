@@ -12,27 +12,175 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::time::{Duration, Instant};
 use poise::{FrameworkContext, FrameworkError, Event};
-use poise::serenity_prelude::{self as serenity, CommandDataOption};
-use crate::base::Data;
-use crate::helper::{Error, Result};
+use poise::serenity_prelude::{self as serenity, CommandDataOption, Message};
+use tracing::{Instrument, Span};
+use crate::{admin, cooldown};
+use crate::base::{Data, Guard};
+use crate::core::permission::{self, Denial};
+use crate::helper::{Context, Error, Result};
 
 pub async fn event_handler(
-    _: &serenity::Context,
+    context: &serenity::Context,
     event: &Event<'_>,
-    _: FrameworkContext<'_, Data, Error>,
-    _: &Data,
+    framework: FrameworkContext<'_, Data, Error>,
+    data: &Data,
 ) -> Result<()> {
+    dispatch(context, event, framework, data).instrument(shard(context)).await
+}
+
+/// Logging context for everything handled on behalf of the shard of `context`.
+fn shard(context: &serenity::Context) -> Span {
+    tracing::info_span!("shard", id = context.shard_id)
+}
+
+async fn dispatch(
+    context: &serenity::Context,
+    event: &Event<'_>,
+    framework: FrameworkContext<'_, Data, Error>,
+    data: &Data,
+) -> Result<()> {
+    data.metrics.events.with_label_values(&[event.name()]).inc();
+
     match event {
-        Event::Ready { .. } => tracing::info!("connection ready"),
-        Event::Resume { .. } => tracing::info!("session resumed"),
+        Event::Ready { .. } => {
+            tracing::info!("connection ready");
+            data.health.mark_setup();
+            data.metrics.sessions.with_label_values(&["ready"]).inc();
+        }
+        Event::Resume { .. } => {
+            tracing::info!("session resumed");
+            data.metrics.sessions.with_label_values(&["resume"]).inc();
+        }
         _ => (),
     };
 
+    data.modules.event(context, event, framework, data).await;
+
     Ok(())
 }
 
+pub async fn prefix<'a>(
+    context: &'a serenity::Context,
+    message: &'a Message,
+    data: &'a Data,
+) -> Result<Option<(&'a str, &'a str)>> {
+    let config = data.config();
+    let content = message.content.as_str();
+
+    // guilds only have prefixes of their own while the admin module is on
+    let guild_prefixes = match (message.guild_id, data.modules.get::<admin::Module>()) {
+        (Some(guild_id), Some(_)) => admin::prefixes(data, guild_id).await?,
+        _ => None,
+    };
+    let mut prefixes = guild_prefixes.unwrap_or_else(|| config.core.prefixes.clone());
+    // longest first, so that "!!" is not shadowed by "!"
+    prefixes.sort_by_key(|prefix| std::cmp::Reverse(prefix.len()));
+
+    if let Some(prefix) = prefixes.iter().find(|prefix| content.starts_with(prefix.as_str())) {
+        return Ok(Some(content.split_at(prefix.len())))
+    }
+
+    if config.core.mention {
+        let user_id = context.cache.current_user_id().to_string();
+        let stripped = content
+            .strip_prefix("<@")
+            .map(|content| content.trim_start_matches('!'))
+            .and_then(|content| content.strip_prefix(user_id.as_str()))
+            .and_then(|content| content.strip_prefix('>'));
+
+        if let Some(stripped) = stripped {
+            return Ok(Some(content.split_at(content.len() - stripped.len())))
+        }
+    }
+
+    Ok(None)
+}
+
+/// Lives as long as the invocation, holding it in flight for graceful shutdown.
+struct Invocation {
+    started: Instant,
+    _guard: Guard,
+}
+
+/// How many commands of the invoked chain passed the global check so far.
+struct Checked(usize);
+
+/// Refuses new commands once shutdown has been triggered, then enforces
+/// per-guild toggles, permission levels and cooldowns.
+///
+/// Poise runs this for each command from the outermost group down to the
+/// invoked one, but every run sees the invoked command, so it only decides
+/// on the last one.
+pub async fn command_check(context: Context<'_>) -> Result<bool> {
+    let checked = context.invocation_data::<Checked>().await.map_or(0, |checked| checked.0) + 1;
+    if checked <= context.parent_commands().len() {
+        context.set_invocation_data(Checked(checked)).await;
+        return Ok(true)
+    }
+
+    if context.data().shutdown.is_triggered() {
+        return permission::deny(context, "shutting down, try again later!").await
+    }
+
+    if !context.data().modules.command_check(context).await? {
+        return Ok(false)
+    }
+
+    if !permission::check(context).await? {
+        return Ok(false)
+    }
+
+    cooldown::command_check(context).await
+}
+
+/// Counts the invocation, uses up its cooldowns and stamps its start so the
+/// duration can be observed later.
+pub async fn pre_command(context: Context<'_>) {
+    begin(context).instrument(shard(context.serenity_context())).await
+}
+
+async fn begin(context: Context<'_>) {
+    let data = context.data();
+    let name = context.command().qualified_name.as_str();
+    data.metrics.commands.with_label_values(&[name]).inc();
+
+    let started = Instant::now();
+    cooldown::record(context, started);
+
+    let invocation = Invocation { started, _guard: data.shutdown.enter() };
+    context.set_invocation_data(invocation).await;
+}
+
+pub async fn post_command(context: Context<'_>) {
+    observe(context).instrument(shard(context.serenity_context())).await
+}
+
 pub async fn on_error(error: FrameworkError<'_, Data, Error>) {
+    let span = shard(error.serenity_context());
+    report(error).instrument(span).await
+}
+
+async fn report(error: FrameworkError<'_, Data, Error>) {
+    if let (Some(context), Some(reason)) = (error.ctx(), reason(&error)) {
+        let name = context.command().qualified_name.as_str();
+        let metrics = &context.data().metrics;
+
+        // checks run before `pre_command`, so refusals were never counted as invocations
+        let started = context.invocation_data::<Invocation>().await.map(|invocation| invocation.started);
+        match started {
+            Some(_) => metrics.failures.with_label_values(&[name, reason]).inc(),
+            None => metrics.denials.with_label_values(&[name, reason]).inc(),
+        }
+
+        // arguments are parsed after `pre_command`, a typo shouldn't cost a use
+        if let (Some(started), "argument") = (started, reason) {
+            cooldown::release(context, started);
+        }
+        observe(context).await;
+    }
+
     match error {
         FrameworkError::Setup { error, .. } => {
             let message = "error building data";
@@ -44,6 +192,9 @@ pub async fn on_error(error: FrameworkError<'_, Data, Error>) {
             tracing::error!(message, event, error);
         }
         FrameworkError::Command { error, ctx } => {
+            let id = correlation_id();
+            let shard = ctx.serenity_context().shard_id;
+
             match ctx {
                 poise::Context::Application(context) => {
                     let message = "error invoking application command";
@@ -59,16 +210,144 @@ pub async fn on_error(error: FrameworkError<'_, Data, Error>) {
                         .collect::<Vec<String>>()
                         .join(" ");
                     let arguments = format!("({options})");
-                    tracing::error!(message, name, arguments, error);
+                    tracing::error!(message, id, shard, name, arguments, error);
                 }
                 poise::Context::Prefix(context) => {
                     let message = "error invoking prefix command";
                     let name = &context.command.qualified_name;
                     let arguments = context.args;
-                    tracing::error!(message, name, arguments, error);
+                    tracing::error!(message, id, shard, name, arguments, error);
+                }
+            };
+
+            let response = format!("something went wrong running this command! (error id `{id}`)");
+            reply(ctx, response).await;
+        }
+        FrameworkError::ArgumentParse { error, input, ctx } => {
+            let name = &ctx.command().qualified_name;
+            let response = match input {
+                Some(input) => format!("couldn't parse `{input}` for `{name}`: {error}!"),
+                None => format!("couldn't parse arguments for `{name}`: {error}!"),
+            };
+            reply(ctx, response).await;
+        }
+        FrameworkError::CommandStructureMismatch { description, ctx } => {
+            let id = correlation_id();
+            let message = "error matching application command structure";
+            let shard = ctx.serenity_context.shard_id;
+            let name = &ctx.command.qualified_name;
+            tracing::error!(message, id, shard, name, description);
+
+            let response = format!("this command is out of date, try again later! (error id `{id}`)");
+            reply(poise::Context::Application(ctx), response).await;
+        }
+        FrameworkError::CooldownHit { remaining_cooldown, ctx } => {
+            reply(ctx, cooldown_response(remaining_cooldown)).await;
+        }
+        FrameworkError::MissingBotPermissions { missing_permissions, ctx } => {
+            let permissions = missing_permissions.get_permission_names().join(", ");
+            let response = format!("i'm missing permissions to run this command: {permissions}!");
+            reply(ctx, response).await;
+        }
+        FrameworkError::MissingUserPermissions { missing_permissions, ctx } => {
+            let response = match missing_permissions {
+                Some(permissions) => {
+                    let permissions = permissions.get_permission_names().join(", ");
+                    format!("you're missing permissions to run this command: {permissions}!")
                 }
+                None => "you're missing permissions to run this command!".to_owned(),
             };
+            reply(ctx, response).await;
+        }
+        FrameworkError::NotAnOwner { ctx } => {
+            reply(ctx, "only bot owners can run this command!".to_owned()).await;
+        }
+        FrameworkError::GuildOnly { ctx } => {
+            reply(ctx, "this command only works in guilds!".to_owned()).await;
+        }
+        FrameworkError::DmOnly { ctx } => {
+            reply(ctx, "this command only works in direct messages!".to_owned()).await;
+        }
+        FrameworkError::NsfwOnly { ctx } => {
+            reply(ctx, "this command only works in nsfw channels!".to_owned()).await;
+        }
+        FrameworkError::CommandCheckFailed { error: Some(error), ctx } => {
+            let id = correlation_id();
+            let message = "error checking command";
+            let shard = ctx.serenity_context().shard_id;
+            let name = &ctx.command().qualified_name;
+            tracing::error!(message, id, shard, name, error);
+
+            let response = format!("something went wrong checking this command! (error id `{id}`)");
+            reply(ctx, response).await;
+        }
+        FrameworkError::CommandCheckFailed { error: None, ctx } => {
+            let response = match ctx.invocation_data::<Denial>().await.as_deref() {
+                Some(Denial::Reason(reason)) => reason.to_string(),
+                Some(Denial::Cooldown(remaining)) => cooldown_response(*remaining),
+                None => "you can't run this command here!".to_owned(),
+            };
+            reply(ctx, response).await;
+        }
+        FrameworkError::DynamicPrefix { error, .. } => {
+            let message = "error resolving prefix";
+            tracing::error!(message, error);
         }
         _ => (),
     };
 }
+
+fn cooldown_response(remaining: Duration) -> String {
+    let seconds = remaining.as_secs_f32().ceil();
+    format!("this command is on cooldown, try again in {seconds}s!")
+}
+
+/// Failure or denial label for errors raised while invoking a command, none for the rest.
+fn reason(error: &FrameworkError<'_, Data, Error>) -> Option<&'static str> {
+    let reason = match error {
+        FrameworkError::Command { .. } => "error",
+        FrameworkError::ArgumentParse { .. } => "argument",
+        FrameworkError::CommandStructureMismatch { .. } => "argument",
+        FrameworkError::CooldownHit { .. } => "cooldown",
+        FrameworkError::MissingBotPermissions { .. } => "permission",
+        FrameworkError::MissingUserPermissions { .. } => "permission",
+        FrameworkError::NotAnOwner { .. } => "permission",
+        FrameworkError::GuildOnly { .. } => "check",
+        FrameworkError::DmOnly { .. } => "check",
+        FrameworkError::NsfwOnly { .. } => "check",
+        FrameworkError::CommandCheckFailed { .. } => "check",
+        _ => return None,
+    };
+
+    Some(reason)
+}
+
+/// Records the time since [`pre_command`], if it ran for this invocation.
+async fn observe(context: Context<'_>) {
+    let started = context
+        .invocation_data::<Invocation>()
+        .await
+        .map(|invocation| invocation.started);
+
+    if let Some(started) = started {
+        let name = context.command().qualified_name.as_str();
+        let elapsed = started.elapsed().as_secs_f64();
+        context.data().metrics.durations.with_label_values(&[name]).observe(elapsed);
+    }
+}
+
+/// Replies ephemerally where the invocation allows it, failures are only logged.
+async fn reply(context: Context<'_>, response: String) {
+    let result = context.send(|reply| reply.content(response).ephemeral(true)).await;
+
+    if let Err(error) = result {
+        let message = "error replying to command";
+        let error = error.to_string();
+        tracing::warn!(message, error);
+    }
+}
+
+/// Short random id tying a user-facing error message to its logged event.
+fn correlation_id() -> String {
+    format!("{:08x}", rand::random::<u32>())
+}
@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod framework;
 mod hook;
+mod permission;
 mod state;
 
-pub use hook::{event_handler, on_error};
-pub use state::Config;
+pub use framework::{framework, intents, options, start};
+pub use hook::{command_check, event_handler, on_error, post_command, pre_command, prefix};
+pub use permission::{bot_admins, deny, level, refuse, required, Denial, Level};
+pub use state::{Config, Shards, BOT_ADMINS};
@@ -0,0 +1,124 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use poise::Command;
+use tokio::time::Duration;
+use poise::serenity_prelude::UserId;
+use crate::base::Data;
+use crate::core::BOT_ADMINS;
+use crate::helper::{Context, Error, Result};
+use crate::storage::{Scope, Storage};
+
+/// Who may run a command, each level includes the ones below it.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Member,
+    GuildAdmin,
+    BotAdmin,
+    Owner,
+}
+
+/// The level a command declares through `custom_data`, members by default.
+pub fn required(command: &Command<Data, Error>) -> Level {
+    command.custom_data.downcast_ref::<Level>().copied().unwrap_or(Level::Member)
+}
+
+/// Why a check refused an invocation, read back by `on_error` for the reply.
+pub enum Denial {
+    Reason(&'static str),
+    Cooldown(Duration),
+}
+
+/// Application owners plus `core.owners` are owners, `core.bot_admins` and
+/// admins added at runtime are bot admins, and members allowed to manage the
+/// current guild are guild admins.
+pub async fn level(context: Context<'_>) -> Result<Level> {
+    let data = context.data();
+    let config = data.config();
+    let user_id = context.author().id;
+
+    if context.framework().options().owners.contains(&user_id) || config.core.owners.contains(&user_id) {
+        return Ok(Level::Owner)
+    }
+
+    if config.core.bot_admins.contains(&user_id) || bot_admins(&data.storage).await?.contains(&user_id) {
+        return Ok(Level::BotAdmin)
+    }
+
+    let manages_guild = match context.author_member().await {
+        Some(member) => member
+            .permissions(context)
+            .is_ok_and(|permissions| permissions.manage_guild()),
+        None => false,
+    };
+
+    if manages_guild {
+        return Ok(Level::GuildAdmin)
+    }
+
+    Ok(Level::Member)
+}
+
+/// Bot admins added through `owner admin add`, persisted across restarts.
+pub async fn bot_admins(storage: &Storage) -> Result<Vec<UserId>> {
+    let admins = storage.get(Scope::Global, &BOT_ADMINS).await?;
+
+    Ok(admins.unwrap_or_default())
+}
+
+/// Passes when the invoker reaches the highest level declared by the command
+/// or any of its parents, recording why otherwise.
+pub async fn check(context: Context<'_>) -> Result<bool> {
+    let required = context
+        .parent_commands()
+        .iter()
+        .copied()
+        .chain([context.command()])
+        .map(required)
+        .max()
+        .unwrap_or(Level::Member);
+
+    if required == Level::Member {
+        return Ok(true)
+    }
+
+    require(context, required).await
+}
+
+/// Passes when the invoker is at least at `required`, recording why otherwise.
+pub async fn require(context: Context<'_>, required: Level) -> Result<bool> {
+    if level(context).await? >= required {
+        return Ok(true)
+    }
+
+    let reason = match required {
+        Level::Owner => "only bot owners can run this command!",
+        Level::BotAdmin => "only bot admins can run this command!",
+        Level::GuildAdmin => "only members who can manage this guild can run this command!",
+        Level::Member => unreachable!(),
+    };
+
+    deny(context, reason).await
+}
+
+/// Fails a check with `reason` as the reply instead of the generic one.
+pub async fn deny(context: Context<'_>, reason: &'static str) -> Result<bool> {
+    refuse(context, Denial::Reason(reason)).await
+}
+
+pub async fn refuse(context: Context<'_>, denial: Denial) -> Result<bool> {
+    context.set_invocation_data(denial).await;
+
+    Ok(false)
+}
@@ -12,9 +12,90 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use serde::Deserialize;
+use poise::serenity_prelude::UserId;
+use serde::{Deserialize, Serialize};
+use crate::helper::Result;
+use crate::storage::Key;
 
-#[derive(Deserialize)]
+pub const BOT_ADMINS: Key<Vec<UserId>> = Key::new("core.bot_admins");
+
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
 pub struct Config {
     pub token: String,
+    pub prefixes: Vec<String>,
+    pub mention: bool,
+    /// Seconds to wait for running commands and tasks when shutting down.
+    pub grace: u64,
+    /// Owners in addition to the application owner or team.
+    pub owners: Vec<UserId>,
+    /// Bot admins in addition to those added with `owner admin add`.
+    pub bot_admins: Vec<UserId>,
+    pub shards: Shards,
+}
+
+/// Which shards this process connects, Discord's recommended count when
+/// `total` is omitted.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Shards {
+    pub total: Option<u64>,
+    /// First and last shard run by this process, both included, so the bot
+    /// can be split across several processes.
+    pub range: Option<[u64; 2]>,
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<()> {
+        if self.token.is_empty() {
+            let message = "`core.token` is not set, provide it in the config file or KANAE__CORE__TOKEN";
+            return Err(message.into())
+        }
+
+        if self.prefixes.iter().any(|prefix| prefix.trim().is_empty()) {
+            return Err("`core.prefixes` must not contain blank prefixes".into())
+        }
+
+        if self.prefixes.is_empty() && !self.mention {
+            return Err("`core.prefixes` must not be empty while `core.mention` is disabled".into())
+        }
+
+        self.shards.validate()
+    }
+}
+
+impl Shards {
+    /// Shards run by this process, unknown until Discord recommends a count
+    /// when autosharding.
+    pub fn expected(&self) -> Option<u64> {
+        match (self.total, self.range) {
+            (_, Some([first, last])) => Some(last - first + 1),
+            (total, None) => total,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match (self.total, self.range) {
+            (Some(0), _) => Err("`core.shards.total` must be at least 1".into()),
+            (None, Some(_)) => Err("`core.shards.range` requires `core.shards.total`".into()),
+            (Some(total), Some([first, last])) if first > last || last >= total => {
+                Err(format!("`core.shards.range` must be within 0 and {}, first to last", total - 1).into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            token: String::new(),
+            prefixes: vec!["/".to_owned()],
+            mention: true,
+            grace: 30,
+            owners: Vec::new(),
+            bot_admins: Vec::new(),
+            shards: Shards::default(),
+        }
+    }
 }
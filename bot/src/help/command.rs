@@ -0,0 +1,221 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use poise::Command;
+use crate::admin;
+use crate::base::Data;
+use crate::core::{self, Level};
+use crate::helper::{self, Context, Error, Result};
+
+const COMMANDS_PER_PAGE: usize = 15;
+
+/// Show the commands you can run, or how to use one of them
+///
+/// Parameters in `<angle brackets>` are required and the ones in `[square brackets]` are optional.
+#[poise::command(prefix_command, slash_command, category = "help")]
+pub async fn help(
+    context: Context<'_>,
+    #[description = "Command or group name"] #[rest] command: Option<String>,
+) -> Result<()> {
+    let level = core::level(context).await?;
+    let mut entries = Vec::new();
+    // commands turned off here are left out, their subcommands may still be on
+    let toggles = context.data().modules.get::<admin::Module>();
+    for entry in visible(context, level) {
+        if toggles.is_none() || admin::allows(context, &entry.command.qualified_name).await? {
+            entries.push(entry);
+        }
+    }
+
+    match command {
+        Some(name) => describe(context, &entries, name.trim()).await,
+        None => overview(context, &entries).await,
+    }
+}
+
+/// A command the invoker may run along with the category of its top-level
+/// command, which is the module it comes from.
+struct Entry<'a> {
+    category: &'a str,
+    command: &'a Command<Data, Error>,
+}
+
+/// Walks the framework commands depth first, skipping whole branches the
+/// invoker can't run so subcommands of hidden groups stay hidden.
+fn visible(context: Context<'_>, level: Level) -> Vec<Entry<'_>> {
+    fn walk<'a>(
+        context: Context<'_>,
+        level: Level,
+        category: &'a str,
+        command: &'a Command<Data, Error>,
+        entries: &mut Vec<Entry<'a>>,
+    ) {
+        if !runnable(context, level, command) {
+            return
+        }
+
+        entries.push(Entry { category, command });
+
+        for subcommand in &command.subcommands {
+            walk(context, level, category, subcommand, entries);
+        }
+    }
+
+    let mut entries = Vec::new();
+
+    for command in &context.framework().options().commands {
+        let category = command.category.unwrap_or(&command.name);
+        walk(context, level, category, command, &mut entries);
+    }
+
+    entries
+}
+
+fn runnable(context: Context<'_>, level: Level, command: &Command<Data, Error>) -> bool {
+    let in_guild = context.guild_id().is_some();
+    let required = match command.owners_only {
+        true => Level::Owner,
+        false => core::required(command),
+    };
+
+    let location = match in_guild {
+        true => !command.dm_only,
+        false => !command.guild_only,
+    };
+
+    !command.hide_in_help
+        && level >= required
+        && location
+        && (command.prefix_action.is_some() || command.slash_action.is_some() || !command.subcommands.is_empty())
+}
+
+async fn overview(context: Context<'_>, entries: &[Entry<'_>]) -> Result<()> {
+    let mut categories = Vec::<(&str, Vec<String>)>::new();
+
+    for entry in entries {
+        let line = match &entry.command.description {
+            Some(description) => format!("`{}` {description}", usage(context, entry.command)),
+            None => format!("`{}`", usage(context, entry.command)),
+        };
+
+        match categories.iter_mut().find(|(category, _)| *category == entry.category) {
+            Some((_, lines)) => lines.push(line),
+            None => categories.push((entry.category, vec![line])),
+        }
+    }
+
+    let pages = categories
+        .iter()
+        .flat_map(|(category, lines)| {
+            lines
+                .chunks(COMMANDS_PER_PAGE)
+                .map(move |lines| format!("**{category}**\n{}", lines.join("\n")))
+        })
+        .collect::<Vec<String>>();
+
+    if pages.is_empty() {
+        context.say("no commands available here!").await?;
+        return Ok(())
+    }
+
+    helper::paginate_embeds(context, "help", &pages).await
+}
+
+async fn describe(context: Context<'_>, entries: &[Entry<'_>], name: &str) -> Result<()> {
+    let name = name.trim_start_matches(context.prefix());
+    let found = entries.iter().find(|entry| entry.command.qualified_name.eq_ignore_ascii_case(name));
+    let Some(Entry { command, .. }) = found else {
+        context.say(format!("no command named `{name}` available here!")).await?;
+        return Ok(())
+    };
+
+    let mut sections = vec![format!("`{}`", usage(context, command))];
+
+    if let Some(description) = &command.description {
+        sections.push(description.clone());
+    }
+
+    if let Some(help_text) = command.help_text {
+        sections.push(help_text());
+    }
+
+    if !command.parameters.is_empty() {
+        let lines = command.parameters
+            .iter()
+            .map(|parameter| {
+                let mut line = format!("`{}`", parameter.name);
+                if let Some(description) = &parameter.description {
+                    line.push_str(&format!(" {description}"));
+                }
+                if !parameter.choices.is_empty() {
+                    let choices = parameter.choices
+                        .iter()
+                        .map(|choice| format!("`{}`", choice.name))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    line.push_str(&format!("\none of {choices}"));
+                }
+                line
+            })
+            .collect::<Vec<String>>();
+        sections.push(format!("**parameters**\n{}", lines.join("\n")));
+    }
+
+    let subcommands = entries
+        .iter()
+        .filter(|entry| command.subcommands.iter().any(|subcommand| std::ptr::eq(subcommand, entry.command)))
+        .map(|entry| match &entry.command.description {
+            Some(description) => format!("`{}` {description}", entry.command.name),
+            None => format!("`{}`", entry.command.name),
+        })
+        .collect::<Vec<String>>();
+
+    if !subcommands.is_empty() {
+        sections.push(format!("**subcommands**\n{}", subcommands.join("\n")));
+    }
+
+    helper::paginate_embeds(context, &command.qualified_name, &[sections.join("\n\n")]).await
+}
+
+/// Prefix commands are shown with the prefix the help was invoked with,
+/// commands only reachable as prefix commands keep the configured one even
+/// when help was asked through a slash command.
+fn usage(context: Context<'_>, command: &Command<Data, Error>) -> String {
+    let prefix = match (context, command.slash_action) {
+        (Context::Application(_), None) => context
+            .data()
+            .config()
+            .core
+            .prefixes
+            .first()
+            .cloned()
+            .unwrap_or_else(|| "@mention ".to_owned()),
+        _ => context.prefix().to_owned(),
+    };
+
+    let mut usage = format!("{prefix}{}", command.qualified_name);
+
+    for parameter in &command.parameters {
+        match parameter.required {
+            true => usage.push_str(&format!(" <{}>", parameter.name)),
+            false => usage.push_str(&format!(" [{}]", parameter.name)),
+        }
+    }
+
+    if !command.subcommands.is_empty() {
+        usage.push_str(" ...");
+    }
+
+    usage
+}
@@ -0,0 +1,30 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use poise::Command;
+use crate::base::{self, Data};
+use crate::help;
+use crate::helper::Error;
+
+pub struct Module;
+
+impl base::Module for Module {
+    fn name(&self) -> &'static str {
+        "help"
+    }
+
+    fn commands(&self) -> Vec<Command<Data, Error>> {
+        vec![help::help()]
+    }
+}
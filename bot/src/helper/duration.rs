@@ -0,0 +1,73 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+/// Formats a duration as its two most significant units, e.g. `3d 4h` or `12m 5s`.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let units = [
+        (seconds / 86400, "d"),
+        (seconds / 3600 % 24, "h"),
+        (seconds / 60 % 60, "m"),
+        (seconds % 60, "s"),
+    ];
+
+    let parts = units
+        .iter()
+        .skip_while(|(value, _)| *value == 0)
+        .take(2)
+        .map(|(value, unit)| format!("{value}{unit}"))
+        .collect::<Vec<String>>();
+
+    if parts.is_empty() {
+        "0s".to_owned()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Parses durations like `90s`, `15m` or `2h30m`, a bare number is read as minutes.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    if let Ok(minutes) = text.parse::<u64>() {
+        return Some(Duration::from_secs(minutes.checked_mul(60)?))
+    }
+
+    let mut seconds = 0u64;
+    let mut digits = String::new();
+
+    for character in text.chars() {
+        if character.is_ascii_digit() {
+            digits.push(character);
+            continue
+        }
+
+        let unit = match character {
+            'd' => 86400,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let value = digits.parse::<u64>().ok()?;
+        seconds = seconds.checked_add(value.checked_mul(unit)?)?;
+        digits.clear();
+    }
+
+    if !digits.is_empty() || text.is_empty() {
+        return None
+    }
+
+    Some(Duration::from_secs(seconds))
+}
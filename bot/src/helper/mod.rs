@@ -13,5 +13,9 @@
 // limitations under the License.
 
 mod alias;
+mod duration;
+mod paginate;
 
 pub use alias::{arcmut, ArcMut, Context, Error, Result};
+pub use duration::{format_duration, parse_duration};
+pub use paginate::{paginate, paginate_embeds};
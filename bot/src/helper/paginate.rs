@@ -0,0 +1,118 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use poise::serenity_prelude::{
+    ButtonStyle, CollectComponentInteraction, CreateComponents, InteractionResponseType,
+};
+use tokio::time::Duration;
+use crate::helper::{Context, Result};
+
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Reply and interaction builders share method names but no trait.
+macro_rules! render {
+    ($builder:expr, $title:expr, $pages:expr, $index:expr) => {
+        match $title {
+            Some(title) => $builder.embed(|embed| {
+                let embed = embed.title(title).description(&$pages[$index]);
+                match $pages.len() {
+                    1 => embed,
+                    _ => embed.footer(|footer| footer.text(format!("page {}/{}", $index + 1, $pages.len()))),
+                }
+            }),
+            None => match $pages.len() {
+                1 => $builder.content(&$pages[$index]),
+                _ => $builder.content(format!("{}\n\npage {}/{}", $pages[$index], $index + 1, $pages.len())),
+            },
+        }
+    };
+}
+
+/// Sends `pages` one at a time with previous and next buttons that only the
+/// invoker can use, the buttons are removed once nobody clicked for a while.
+pub async fn paginate(context: Context<'_>, pages: &[String]) -> Result<()> {
+    show(context, None, pages).await
+}
+
+/// Same as [`paginate`] with every page rendered as an embed under `title`.
+pub async fn paginate_embeds(context: Context<'_>, title: &str, pages: &[String]) -> Result<()> {
+    show(context, Some(title), pages).await
+}
+
+async fn show(context: Context<'_>, title: Option<&str>, pages: &[String]) -> Result<()> {
+    if pages.is_empty() {
+        return Ok(())
+    }
+
+    if pages.len() == 1 {
+        context.send(|reply| render!(reply, title, pages, 0)).await?;
+        return Ok(())
+    }
+
+    let id = context.id();
+    let previous = format!("{id}.previous");
+    let next = format!("{id}.next");
+
+    let handle = context
+        .send(|reply| {
+            render!(reply, title, pages, 0).components(|components| buttons(components, &previous, &next))
+        })
+        .await?;
+
+    let token = context.data().shutdown.token();
+    let mut index = 0;
+    loop {
+        let collector = CollectComponentInteraction::new(context)
+            .author_id(context.author().id)
+            .channel_id(context.channel_id())
+            .timeout(TIMEOUT)
+            .filter({
+                let (previous, next) = (previous.clone(), next.clone());
+                move |interaction| interaction.data.custom_id == previous || interaction.data.custom_id == next
+            });
+
+        // the buttons are removed right away on shutdown instead of holding up the drain
+        let interaction = tokio::select!{
+            interaction = collector => interaction,
+            _ = token.cancelled() => None,
+        };
+        let Some(interaction) = interaction else {
+            break
+        };
+
+        index = match interaction.data.custom_id == next {
+            true => (index + 1) % pages.len(),
+            false => (index + pages.len() - 1) % pages.len(),
+        };
+
+        interaction
+            .create_interaction_response(context, |response| {
+                response
+                    .kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|data| render!(data, title, pages, index))
+            })
+            .await?;
+    }
+
+    handle.edit(context, |reply| reply.components(|components| components)).await?;
+
+    Ok(())
+}
+
+fn buttons<'a>(components: &'a mut CreateComponents, previous: &str, next: &str) -> &'a mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|button| button.custom_id(previous).label("<").style(ButtonStyle::Secondary))
+            .create_button(|button| button.custom_id(next).label(">").style(ButtonStyle::Secondary))
+    })
+}
@@ -0,0 +1,225 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use poise::serenity_prelude::{Http, UserId};
+use serde_json::json;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::time::Duration;
+use tracing::{Event, Level, Subscriber};
+use tracing::field::{Field, Visit};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+use crate::base::Reloader;
+use crate::helper::Result;
+use crate::log::AlertConfig;
+
+const MAX_LENGTH: usize = 1900;
+/// Alerts waiting to be delivered, more are dropped during an error storm.
+const CAPACITY: usize = 64;
+
+/// An ERROR-level event captured for forwarding.
+pub struct Alert {
+    pub message: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl Alert {
+    /// Identifies repeats of the same failure, ignoring per-invocation ids.
+    fn key(&self) -> String {
+        let fields = self.fields
+            .iter()
+            .filter(|(name, _)| name != "id")
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        format!("{} {fields}", self.message)
+    }
+
+    fn render(&self, suppressed: usize) -> String {
+        let mut content = format!("**error** {}", self.message);
+
+        for (name, value) in &self.fields {
+            let line = format!("\n`{name}`: {value}");
+            content.push_str(&line);
+        }
+
+        if suppressed > 0 {
+            let line = format!("\n*{suppressed} similar alerts were suppressed*");
+            content.push_str(&line);
+        }
+
+        if content.len() > MAX_LENGTH {
+            let mut end = MAX_LENGTH;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            content.truncate(end);
+            content.push('…');
+        }
+
+        content
+    }
+}
+
+/// Captures ERROR events into a channel drained by [`Alerter::run`].
+pub struct AlertLayer {
+    sender: Sender<Alert>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl<S: Subscriber> Layer<S> for AlertLayer {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let metadata = event.metadata();
+
+        // failures delivering alerts must not feed back into the sink
+        if *metadata.level() != Level::ERROR || metadata.target() == module_path!() {
+            return
+        }
+
+        let mut alert = Alert { message: String::new(), fields: Vec::new() };
+        event.record(&mut alert);
+        if self.sender.try_send(alert).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Visit for Alert {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = value.to_owned(),
+            name => self.fields.push((name.to_owned(), value.to_owned())),
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.record_str(field, &format!("{value:?}"));
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.record_str(field, &value.to_string());
+    }
+}
+
+/// Rate limits alerts to `burst` per `period` and drops repeats within `dedup`.
+pub struct Throttle {
+    sent: Vec<Instant>,
+    seen: HashMap<String, Instant>,
+    suppressed: usize,
+}
+
+impl Throttle {
+    pub fn new() -> Self {
+        Throttle { sent: Vec::new(), seen: HashMap::new(), suppressed: 0 }
+    }
+
+    /// Returns how many alerts were suppressed since the last admitted one,
+    /// or `None` when this one must be dropped.
+    pub fn admit(&mut self, key: String, now: Instant, config: &AlertConfig) -> Option<usize> {
+        let period = Duration::from_secs(config.period);
+        let dedup = Duration::from_secs(config.dedup);
+
+        self.sent.retain(|sent| now.duration_since(*sent) < period);
+        self.seen.retain(|_, seen| now.duration_since(*seen) < dedup);
+
+        if self.seen.contains_key(&key) || self.sent.len() >= config.burst {
+            self.suppressed += 1;
+            return None
+        }
+
+        self.sent.push(now);
+        self.seen.insert(key, now);
+
+        Some(std::mem::take(&mut self.suppressed))
+    }
+}
+
+pub struct Alerter {
+    receiver: Receiver<Alert>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl Alerter {
+    pub fn new() -> (AlertLayer, Alerter) {
+        let (sender, receiver) = mpsc::channel(CAPACITY);
+        let dropped = Arc::new(AtomicUsize::new(0));
+
+        (AlertLayer { sender, dropped: dropped.clone() }, Alerter { receiver, dropped })
+    }
+
+    /// Forwards captured alerts until the logger is dropped, reading the
+    /// sink settings from the live config so they can be reloaded.
+    ///
+    /// `owners` are the application owners, resolved once at startup and
+    /// joined by `core.owners`.
+    pub async fn run(mut self, reloader: Reloader, http: Arc<Http>, owners: HashSet<UserId>) {
+        let client = reqwest::Client::new();
+        let mut throttle = Throttle::new();
+
+        while let Some(alert) = self.receiver.recv().await {
+            let config = reloader.config();
+            let owners = owners.iter().chain(&config.core.owners).copied().collect::<HashSet<UserId>>();
+            let config = &config.log.alert;
+
+            if config.webhook.is_none() && !config.owners {
+                continue
+            }
+
+            let Some(suppressed) = throttle.admit(alert.key(), Instant::now(), config) else {
+                continue
+            };
+            let dropped = self.dropped.swap(0, Ordering::Relaxed);
+            let content = alert.render(suppressed + dropped);
+
+            if let Some(webhook) = &config.webhook {
+                if let Err(error) = send_webhook(&client, webhook, &content).await {
+                    let message = "error sending alert to webhook";
+                    tracing::error!(message, error);
+                }
+            }
+
+            if config.owners {
+                if let Err(error) = send_owners(&http, &owners, &content).await {
+                    let message = "error sending alert to owners";
+                    tracing::error!(message, error);
+                }
+            }
+        }
+    }
+}
+
+async fn send_webhook(client: &reqwest::Client, url: &str, content: &str) -> Result<()> {
+    let body = json!({
+        "content": content,
+        "allowed_mentions": { "parse": [] },
+    });
+
+    client.post(url).json(&body).send().await?.error_for_status()?;
+
+    Ok(())
+}
+
+async fn send_owners(http: &Http, owners: &HashSet<UserId>, content: &str) -> Result<()> {
+    for owner in owners {
+        owner.create_dm_channel(http).await?.say(http, content).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::IsTerminal;
+use std::sync::Mutex;
+use tracing::{Level, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::fmt::time::UtcTime;
+use tracing_subscriber::fmt::writer::MakeWriterExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use crate::helper::Result;
+use crate::log::{Alerter, Config, Format};
+
+type Handle = reload::Handle<EnvFilter, Registry>;
+
+/// Keeps the non-blocking writers alive and the filter reloadable.
+pub struct Logger {
+    handle: Handle,
+    guards: Mutex<Vec<WorkerGuard>>,
+}
+
+impl Logger {
+    pub fn reload(&self, config: &Config) -> Result<()> {
+        let filter = EnvFilter::try_new(&config.filter)?;
+        self.handle.reload(filter)?;
+
+        Ok(())
+    }
+
+    /// A logger that was never installed as the global subscriber, for tests
+    /// that need a [`Reloader`](crate::base::Reloader) but no output.
+    #[cfg(test)]
+    pub fn detached() -> Logger {
+        let (_, handle) = reload::Layer::new(EnvFilter::default());
+
+        Logger { handle, guards: Mutex::new(Vec::new()) }
+    }
+
+    /// Drops the writer guards, blocking until buffered lines are written.
+    /// Events logged afterwards are lost, so call this right before exiting.
+    pub fn flush(&self) {
+        self.guards.lock().unwrap().clear();
+    }
+}
+
+/// Installs the global subscriber, returning the [`Alerter`] that forwards
+/// ERROR events once the runtime and HTTP client are available.
+pub fn init(config: &Config) -> Result<(Logger, Alerter)> {
+    let (filter, handle) = reload::Layer::new(EnvFilter::try_new(&config.filter)?);
+    let mut guards = Vec::new();
+
+    let (outwriter, outguard) = tracing_appender::non_blocking(std::io::stdout());
+    let outwriter = outwriter.with_min_level(Level::INFO);
+    let (errwriter, errguard) = tracing_appender::non_blocking(std::io::stderr());
+    let errwriter = errwriter.with_max_level(Level::WARN);
+    guards.extend([outguard, errguard]);
+
+    let ansi = config.ansi.unwrap_or_else(|| std::io::stdout().is_terminal());
+    let console = layer(config.format, ansi, outwriter.and(errwriter));
+
+    let file = match &config.file {
+        Some(file) => {
+            std::fs::create_dir_all(&file.directory)?;
+            let appender = RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .filename_prefix(&file.prefix)
+                .max_log_files(file.retention)
+                .build(&file.directory)?;
+            let (filewriter, fileguard) = tracing_appender::non_blocking(appender);
+            guards.push(fileguard);
+
+            Some(layer(config.format, false, filewriter))
+        }
+        None => None,
+    };
+
+    let (alert, alerter) = Alerter::new();
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(console)
+        .with(file)
+        .with(alert)
+        .try_init()?;
+
+    Ok((Logger { handle, guards: Mutex::new(guards) }, alerter))
+}
+
+fn layer<S, W>(format: Format, ansi: bool, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let description = time::macros::format_description!(
+        "[day]/[month]/[year] \
+        [hour repr:24]:[minute]:[second].[subsecond digits:3]"
+    );
+    let timer = UtcTime::new(description);
+    let layer = tracing_subscriber::fmt::layer()
+        .with_ansi(ansi)
+        .with_timer(timer)
+        .with_target(false)
+        .with_writer(writer);
+
+    match format {
+        Format::Compact => layer.compact().boxed(),
+        Format::Pretty => layer.pretty().boxed(),
+        Format::Json => layer.json().boxed(),
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::EnvFilter;
+use crate::helper::Result;
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub format: Format,
+    pub filter: String,
+    pub ansi: Option<bool>,
+    pub file: Option<File>,
+    pub alert: AlertConfig,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Compact,
+    Pretty,
+    Json,
+}
+
+/// Daily rotated log files, keeping at most `retention` of them.
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct File {
+    pub directory: String,
+    #[serde(default = "File::default_prefix")]
+    pub prefix: String,
+    #[serde(default = "File::default_retention")]
+    pub retention: usize,
+}
+
+/// Where ERROR events are forwarded, at most `burst` per `period` seconds and
+/// with repeats of the same event dropped for `dedup` seconds.
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    pub webhook: Option<String>,
+    pub owners: bool,
+    pub burst: usize,
+    pub period: u64,
+    pub dedup: u64,
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<()> {
+        EnvFilter::try_new(&self.filter)
+            .map_err(|error| format!("`log.filter` is not a valid filter: {error}"))?;
+
+        if let Some(file) = &self.file {
+            if file.retention == 0 {
+                return Err("`log.file.retention` must be at least 1".into())
+            }
+        }
+
+        if let Some(webhook) = &self.alert.webhook {
+            reqwest::Url::parse(webhook)
+                .map_err(|error| format!("`log.alert.webhook` is not a valid url: {error}"))?;
+        }
+
+        if self.alert.burst == 0 || self.alert.period == 0 {
+            return Err("`log.alert.burst` and `log.alert.period` must be at least 1".into())
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            format: Format::Compact,
+            filter: "bot=debug".to_owned(),
+            ansi: None,
+            file: None,
+            alert: AlertConfig::default(),
+        }
+    }
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        AlertConfig {
+            webhook: None,
+            owners: false,
+            burst: 5,
+            period: 60,
+            dedup: 600,
+        }
+    }
+}
+
+impl File {
+    fn default_prefix() -> String {
+        "bot.log".to_owned()
+    }
+
+    fn default_retention() -> usize {
+        7
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Instant;
+use hyper::{Method, StatusCode};
+use poise::serenity_prelude::Http;
+use serde_json::Value;
+use tokio::time::Duration;
+use tracing_subscriber::layer::SubscriberExt;
+use crate::base::{Config, Reloader, Source};
+use crate::log::{AlertConfig, Alerter, Logger};
+use crate::log::alert::Throttle;
+use crate::mock::Rest;
+
+const WEBHOOK: &str = "/webhooks/1/token";
+
+fn config(burst: usize, period: u64, dedup: u64) -> AlertConfig {
+    AlertConfig { webhook: None, owners: false, burst, period, dedup }
+}
+
+#[test]
+fn throttle_admits_a_burst_per_period() {
+    let config = config(2, 60, 0);
+    let mut throttle = Throttle::new();
+    let start = Instant::now();
+
+    assert_eq!(throttle.admit("a".to_owned(), start, &config), Some(0));
+    assert_eq!(throttle.admit("b".to_owned(), start, &config), Some(0));
+    assert_eq!(throttle.admit("c".to_owned(), start, &config), None);
+    assert_eq!(throttle.admit("d".to_owned(), start + Duration::from_secs(59), &config), None);
+
+    // the next admitted alert carries how many were dropped before it
+    assert_eq!(throttle.admit("e".to_owned(), start + Duration::from_secs(60), &config), Some(2));
+}
+
+#[test]
+fn throttle_drops_repeats_within_dedup() {
+    let config = config(10, 60, 600);
+    let mut throttle = Throttle::new();
+    let start = Instant::now();
+
+    assert_eq!(throttle.admit("a".to_owned(), start, &config), Some(0));
+    assert_eq!(throttle.admit("a".to_owned(), start + Duration::from_secs(599), &config), None);
+    assert_eq!(throttle.admit("b".to_owned(), start + Duration::from_secs(599), &config), Some(1));
+    assert_eq!(throttle.admit("a".to_owned(), start + Duration::from_secs(600), &config), Some(0));
+}
+
+/// Starts forwarding alerts to a webhook on a local [`Rest`] stand-in.
+async fn webhook(alert: AlertConfig) -> (Rest, Reloader) {
+    let rest = Rest::start(String::new(), Value::Null).await;
+    rest.respond(Method::POST, WEBHOOK, StatusCode::OK, Value::Null);
+
+    let mut config = Config::default();
+    config.log.alert = AlertConfig { webhook: Some(format!("{}{WEBHOOK}", rest.url())), ..alert };
+    let source = Source { path: "mock.toml".into(), explicit: false };
+    let reloader = Reloader::new(source, config, Arc::new(Logger::detached()));
+
+    (rest, reloader)
+}
+
+#[tokio::test]
+async fn errors_are_posted_to_the_webhook() {
+    let (rest, reloader) = webhook(config(5, 60, 600)).await;
+    let (layer, alerter) = Alerter::new();
+    let run = tokio::spawn(alerter.run(reloader, Arc::new(Http::new("mock")), HashSet::new()));
+
+    tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+        let message = "error dispatching handler";
+        let error = "boom";
+        tracing::error!(message, error);
+        tracing::error!(message, error);
+        tracing::warn!("not forwarded");
+        let error = "bang";
+        tracing::error!(message, error);
+    });
+
+    let call = rest.wait(Method::POST, WEBHOOK).await;
+    assert_eq!(call.body["content"], "**error** error dispatching handler\n`error`: boom");
+    assert_eq!(call.body["allowed_mentions"]["parse"], Value::Array(Vec::new()));
+
+    let call = rest.wait(Method::POST, WEBHOOK).await;
+    let content = "**error** error dispatching handler\n`error`: bang\n*1 similar alerts were suppressed*";
+    assert_eq!(call.body["content"], content);
+
+    run.abort();
+    assert!(rest.calls().is_empty());
+}
+
+#[tokio::test]
+async fn alerts_beyond_capacity_are_dropped() {
+    let (rest, reloader) = webhook(config(100, 60, 600)).await;
+    let (layer, alerter) = Alerter::new();
+
+    // nothing drains the queue yet, so only the first 64 fit
+    tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+        for error in 0..66 {
+            tracing::error!(error, "error storm");
+        }
+    });
+
+    let run = tokio::spawn(alerter.run(reloader, Arc::new(Http::new("mock")), HashSet::new()));
+
+    let call = rest.wait(Method::POST, WEBHOOK).await;
+    let content = "**error** error storm\n`error`: 0\n*2 similar alerts were suppressed*";
+    assert_eq!(call.body["content"], content);
+
+    run.abort();
+}
@@ -12,47 +12,38 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod admin;
 mod base;
+mod cooldown;
 mod core;
+mod help;
 mod helper;
+mod log;
+mod metrics;
+#[cfg(test)]
+mod mock;
 mod owner;
+mod server;
+mod storage;
 
-use poise::{Framework, FrameworkOptions, PrefixFrameworkOptions};
-use poise::serenity_prelude::GatewayIntents;
+use std::collections::HashSet;
+use std::sync::Arc;
+use poise::serenity_prelude::ClientBuilder;
 use tokio::runtime::Builder;
-use tracing::Level;
-use tracing_subscriber::fmt::time::UtcTime;
-use tracing_subscriber::fmt::writer::MakeWriterExt;
+use tokio::time::Duration;
 
 fn main() {
-    let (outwriter, _outguard) = tracing_appender::non_blocking(std::io::stdout());
-    let outwriter = outwriter.with_min_level(Level::INFO);
-    let (errwriter, _errguard) = tracing_appender::non_blocking(std::io::stderr());
-    let errwriter = errwriter.with_max_level(Level::WARN);
-    let writer = outwriter
-        .and(errwriter)
-        .with_filter(|metadata| metadata
-            .module_path()
-            .map_or(false, |path| path.contains("bot"))
-        );
-
-    let description = time::macros::format_description!(
-        "[day]/[month]/[year] \
-        [hour repr:24]:[minute]:[second].[subsecond digits:3]"
-    );
-    let timer = UtcTime::new(description);
-    let format = tracing_subscriber::fmt::format()
-        .with_ansi(true)
-        .with_timer(timer)
-        .with_target(false)
-        .compact();
-
-    tracing_subscriber::fmt()
-        .with_max_level(Level::DEBUG)
-        .with_writer(writer)
-        .event_format(format)
-        .try_init()
-        .expect("error initializing logger");
+    let loaded = base::Source::from_env().and_then(|source| Ok((base::config(&source)?, source)));
+    let (config, source) = match loaded {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            eprintln!("error loading config: {error}");
+            std::process::exit(1);
+        }
+    };
+
+    let (logger, alerter) = log::init(&config.log).expect("error initializing logger");
+    let logger = Arc::new(logger);
 
     let runtime = Builder::new_multi_thread()
         .thread_name("bot")
@@ -60,67 +51,133 @@ fn main() {
         .build()
         .expect("error building runtime");
 
-    runtime.block_on(run());
+    let reloader = base::Reloader::new(source, config, logger.clone());
+    let code = runtime.block_on(run(reloader, alerter));
+    drop(runtime);
+
+    logger.flush();
+    std::process::exit(code);
 }
 
-async fn run() {
-    let config = base::config("bot/config.toml").expect("error finding bot/config.toml");
-    let intents = GatewayIntents::empty()
-        | GatewayIntents::GUILD_MESSAGES
-        | GatewayIntents::MESSAGE_CONTENT;
+async fn run(reloader: base::Reloader, alerter: log::Alerter) -> i32 {
+    let token = reloader.config().core.token.clone();
+    let shards = reloader.config().core.shards;
 
-    let commands = vec![owner::group()];
-    let prefix_options = PrefixFrameworkOptions {
-        prefix: Some("/".to_owned()),
-        ..Default::default()
-    };
-    let options = FrameworkOptions {
-        commands,
-        on_error: |e| Box::pin(core::on_error(e)),
-        event_handler: |c, e, f, d| Box::pin(core::event_handler(c, e, f, d)),
-        prefix_options,
-        ..Default::default()
+    let storage = storage::Storage::open(&reloader.config().storage).expect("error opening storage");
+    storage.migrate().await.expect("error migrating storage");
+
+    let shared = base::Shared {
+        reloader: reloader.clone(),
+        metrics: Arc::new(metrics::Metrics::new().expect("error building metrics")),
+        health: Arc::new(server::Health::default()),
+        storage,
+        shutdown: base::Shutdown::default(),
+        modules: Arc::new(base::registry().enabled(&reloader.config())),
     };
 
-    let framework = Framework::builder()
-        .token(&config.core.token)
-        .intents(intents)
-        .options(options)
-        .setup(|c, r, f| Box::pin(async move { base::data(c, r, f).await }))
-        .build().await
-        .expect("error building framework");
+    let message = "enabling modules";
+    let modules = shared.modules.names().join(", ");
+    tracing::info!(message, modules);
 
-    let shard_manager = framework.shard_manager().clone();
+    let mut owners = HashSet::new();
+    if let Err(error) = poise::insert_owners_from_http(&token, &mut owners).await {
+        let message = "error fetching application owners";
+        let error = error.to_string();
+        tracing::warn!(message, error);
+    }
 
-    tokio::spawn(async move {
-        #[cfg(unix)] {
-            use tokio::signal::unix::{self, SignalKind};
+    let options = core::options(shared.modules.commands(), owners.clone());
+    let client = ClientBuilder::new(&token, core::intents());
+    let framework = core::framework(client, options, shared.clone()).await.expect("error building framework");
 
-            let mut hangup = unix::signal(SignalKind::hangup()).expect("error listening SIGHUP");
-            let mut interrupt = unix::signal(SignalKind::interrupt()).expect("error listening SIGINT");
-            let mut terminate = unix::signal(SignalKind::terminate()).expect("error listening SIGTERM");
+    let shard_manager = framework.shard_manager().clone();
 
-            tokio::select!{
-                s = hangup.recv() => s.unwrap(),
-                s = interrupt.recv() => s.unwrap(),
-                s = terminate.recv() => s.unwrap(),
-            };
+    let cache = framework.client().cache_and_http.cache.clone();
+    let http = framework.client().cache_and_http.http.clone();
+
+    let base::Shared { metrics, health, storage, shutdown, .. } = shared;
+
+    shutdown.spawn(reloader.clone().watch());
+    shutdown.spawn(metrics::collect(metrics.clone(), shard_manager.clone(), cache.clone()));
+    tokio::spawn(alerter.run(reloader.clone(), http, owners));
+
+    if let Some(address) = reloader.config().server.address {
+        let shards = shards.expected();
+        let state = server::State { metrics, health, shard_manager: shard_manager.clone(), cache, shards };
+        tokio::spawn(server::serve(address, Arc::new(state)));
+    }
+
+    tokio::spawn({
+        let reloader = reloader.clone();
+        let shutdown = shutdown.clone();
+        async move {
+            #[cfg(unix)] {
+                use tokio::signal::unix::{self, SignalKind};
+
+                let mut hangup = unix::signal(SignalKind::hangup()).expect("error listening SIGHUP");
+                let mut interrupt = unix::signal(SignalKind::interrupt()).expect("error listening SIGINT");
+                let mut terminate = unix::signal(SignalKind::terminate()).expect("error listening SIGTERM");
+
+                loop {
+                    tokio::select!{
+                        s = hangup.recv() => s.unwrap(),
+                        s = interrupt.recv() => break s.unwrap(),
+                        s = terminate.recv() => break s.unwrap(),
+                    };
+
+                    if let Err(error) = reloader.reload() {
+                        let message = "error reloading config, keeping previous one";
+                        tracing::error!(message, error);
+                    }
+                }
+            }
+
+            #[cfg(windows)] {
+                use tokio::signal::windows;
+
+                let ctrl_break = windows::ctrl_break().expect("error listening CTRL-BREAK");
+                let ctrl_c = windows::ctrl_c().expect("error listening CTRL-C");
+
+                tokio::select!{
+                    s = ctrl_break.recv() => s.unwrap(),
+                    s = ctrl_c.recv() => s.unwrap(),
+                };
+            }
+
+            shutdown.trigger();
         }
+    });
 
-        #[cfg(windows)] {
-            use tokio::signal::windows;
-
-            let ctrl_break = windows::ctrl_break().expect("error listening CTRL-BREAK");
-            let ctrl_c = windows::ctrl_c().expect("error listening CTRL-C");
-
-            tokio::select!{
-                s = ctrl_break.recv() => s.unwrap(),
-                s = ctrl_c.recv() => s.unwrap(),
-            };
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        let framework = framework.clone();
+        async move {
+            shutdown.triggered().await;
+            tracing::info!("shutting down");
+
+            let grace = Duration::from_secs(reloader.config().core.grace);
+            let remaining = shutdown.drain(grace).await;
+            if remaining > 0 {
+                let message = "grace period elapsed, abandoning running tasks";
+                tracing::warn!(message, remaining);
+            }
+
+            // data, and with it module state, only exists once the first
+            // `Ready` went through setup, so a zero timeout skips waiting for it
+            if let Ok(data) = tokio::time::timeout(Duration::ZERO, framework.user_data()).await {
+                data.modules.shutdown(data).await;
+            }
+
+            if let Err(error) = storage.flush().await {
+                let message = "error flushing storage";
+                tracing::error!(message, error);
+            }
+
+            shard_manager.lock().await.shutdown_all().await;
         }
-
-        shard_manager.lock().await.shutdown_all().await;
     });
 
-    framework.start().await.expect("error starting framework");
+    core::start(framework, shards).await.expect("error starting framework");
+
+    shutdown.code()
 }
@@ -0,0 +1,39 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use poise::serenity_prelude::{Cache, ShardManager};
+use tokio::sync::Mutex;
+use tokio::time::Duration;
+use crate::metrics::Metrics;
+
+const INTERVAL: Duration = Duration::from_secs(15);
+
+/// Samples values that have no event of their own, like shard latency.
+pub async fn collect(metrics: Arc<Metrics>, shard_manager: Arc<Mutex<ShardManager>>, cache: Arc<Cache>) {
+    let mut interval = tokio::time::interval(INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let runners = shard_manager.lock().await.runners.clone();
+        for (shard_id, runner) in runners.lock().await.iter() {
+            let shard = shard_id.0.to_string();
+            let latency = runner.latency.map_or(f64::NAN, |latency| latency.as_secs_f64());
+            metrics.latency.with_label_values(&[&shard]).set(latency);
+        }
+
+        metrics.guilds.set(cache.guild_count() as i64);
+    }
+}
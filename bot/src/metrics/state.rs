@@ -0,0 +1,99 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+use crate::helper::Result;
+
+/// Prometheus collectors shared by the framework hooks and the HTTP server.
+pub struct Metrics {
+    registry: Registry,
+    pub commands: IntCounterVec,
+    pub failures: IntCounterVec,
+    pub denials: IntCounterVec,
+    pub durations: HistogramVec,
+    pub events: IntCounterVec,
+    pub sessions: IntCounterVec,
+    pub latency: GaugeVec,
+    pub guilds: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("bot".to_owned()), None)?;
+
+        let commands = IntCounterVec::new(
+            Opts::new("commands_invoked_total", "Commands invoked"),
+            &["command"],
+        )?;
+        let failures = IntCounterVec::new(
+            Opts::new("commands_failed_total", "Invoked commands failed, by reason"),
+            &["command", "reason"],
+        )?;
+        let denials = IntCounterVec::new(
+            Opts::new("commands_denied_total", "Commands refused before invocation, by reason"),
+            &["command", "reason"],
+        )?;
+        let durations = HistogramVec::new(
+            HistogramOpts::new("command_duration_seconds", "Command execution time"),
+            &["command"],
+        )?;
+        let events = IntCounterVec::new(
+            Opts::new("gateway_events_total", "Gateway events received"),
+            &["event"],
+        )?;
+        let sessions = IntCounterVec::new(
+            Opts::new("gateway_sessions_total", "Gateway sessions started or resumed"),
+            &["kind"],
+        )?;
+        let latency = GaugeVec::new(
+            Opts::new("shard_latency_seconds", "Heartbeat latency per shard"),
+            &["shard"],
+        )?;
+        let guilds = IntGauge::new("guilds", "Guilds in cache")?;
+
+        registry.register(Box::new(commands.clone()))?;
+        registry.register(Box::new(failures.clone()))?;
+        registry.register(Box::new(denials.clone()))?;
+        registry.register(Box::new(durations.clone()))?;
+        registry.register(Box::new(events.clone()))?;
+        registry.register(Box::new(sessions.clone()))?;
+        registry.register(Box::new(latency.clone()))?;
+        registry.register(Box::new(guilds.clone()))?;
+
+        let metrics = Metrics {
+            registry,
+            commands,
+            failures,
+            denials,
+            durations,
+            events,
+            sessions,
+            latency,
+            guilds,
+        };
+
+        Ok(metrics)
+    }
+
+    /// Encodes every collector in the Prometheus text exposition format.
+    pub fn render(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+
+        Ok(String::from_utf8(buffer)?)
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use poise::serenity_prelude::{Attachment, GuildId, HttpError, SerenityError, StatusCode};
+use poise::serenity_prelude::http::error::ErrorResponse;
+use serde_json::{json, Value};
+use tokio::task::JoinHandle;
+use crate::helper::{ArcMut, Result};
+use crate::owner::{self, CacheSize, Discord, Guild, Presence, Rotation, Schedule, Shard};
+
+/// A [`Discord`] that records what commands ask of it and serves canned
+/// files, guilds, commands and shards instead.
+#[derive(Default)]
+pub struct Recorder {
+    /// Bytes served for attachments and downloads, by url.
+    pub files: HashMap<String, Vec<u8>>,
+    pub guild_id: Option<GuildId>,
+    pub guilds: Vec<Guild>,
+    pub cache_size: CacheSize,
+    /// Commands of the bot, returned for any `admin_guild`.
+    pub commands: Vec<Value>,
+    pub shards: Vec<Shard>,
+    pub shard_total: u64,
+    /// Message discord rejects profile edits with, they succeed when unset.
+    pub rejection: Option<String>,
+    pub replies: Mutex<Vec<String>>,
+    /// Every paginated listing, page by page.
+    pub pages: Mutex<Vec<Vec<String>>>,
+    pub presences: Mutex<Vec<Presence>>,
+    pub rotations: Mutex<Vec<Rotation>>,
+    pub schedules: Mutex<Vec<Schedule>>,
+    /// Registered application commands, by guild or `None` for global ones.
+    pub registered: Mutex<HashMap<Option<GuildId>, Vec<Value>>>,
+    pub edits: Mutex<Vec<(String, Value)>>,
+    pub left: Mutex<Vec<GuildId>>,
+    pub restarted: Mutex<Vec<u64>>,
+}
+
+impl Recorder {
+    pub fn replies(&self) -> Vec<String> {
+        self.replies.lock().unwrap().clone()
+    }
+
+    pub fn edits(&self) -> Vec<(String, Value)> {
+        self.edits.lock().unwrap().clone()
+    }
+
+    /// A task standing in for the ones production spawns, it never finishes.
+    fn pending() -> JoinHandle<()> {
+        tokio::spawn(std::future::pending())
+    }
+}
+
+#[poise::async_trait]
+impl Discord for Recorder {
+    async fn reply(&self, content: impl Into<String> + Send + 'async_trait) -> Result<()> {
+        self.replies.lock().unwrap().push(content.into());
+
+        Ok(())
+    }
+
+    async fn reply_quietly(&self, content: String) -> Result<()> {
+        self.reply(content).await
+    }
+
+    async fn paginate(&self, pages: &[String]) -> Result<()> {
+        self.pages.lock().unwrap().push(pages.to_vec());
+
+        Ok(())
+    }
+
+    fn guild_id(&self) -> Option<GuildId> {
+        self.guild_id
+    }
+
+    async fn attachment(&self, attachment: &Attachment) -> Result<Vec<u8>> {
+        match self.files.get(&attachment.url) {
+            Some(bytes) => Ok(bytes.clone()),
+            None => Err(format!("no file at {}", attachment.url).into()),
+        }
+    }
+
+    async fn download(&self, url: &str) -> std::result::Result<Vec<u8>, String> {
+        match self.files.get(url) {
+            Some(bytes) => Ok(bytes.clone()),
+            None => Err("couldn't download the image: 404 not found!".to_owned()),
+        }
+    }
+
+    async fn set_presence(&self, presence: &Presence) {
+        self.presences.lock().unwrap().push(presence.clone());
+    }
+
+    fn spawn_rotation(&self, rotation: Rotation, _: &ArcMut<owner::Data>) -> JoinHandle<()> {
+        self.rotations.lock().unwrap().push(rotation);
+
+        Recorder::pending()
+    }
+
+    fn spawn_schedule(&self, schedule: Schedule) -> JoinHandle<()> {
+        self.schedules.lock().unwrap().push(schedule);
+
+        Recorder::pending()
+    }
+
+    fn commands(&self, _: bool) -> Vec<Value> {
+        self.commands.clone()
+    }
+
+    async fn registered_commands(&self, guild_id: Option<GuildId>) -> Result<Vec<Value>> {
+        Ok(self.registered.lock().unwrap().get(&guild_id).cloned().unwrap_or_default())
+    }
+
+    async fn register_commands(&self, guild_id: Option<GuildId>, commands: Vec<Value>) -> Result<()> {
+        self.registered.lock().unwrap().insert(guild_id, commands);
+
+        Ok(())
+    }
+
+    async fn edit_profile(&self, field: &str, value: Value) -> std::result::Result<(), SerenityError> {
+        if let Some(message) = &self.rejection {
+            let response = ErrorResponse {
+                status_code: StatusCode::BAD_REQUEST,
+                url: "https://discord.com/api/v10/users/@me".parse().unwrap(),
+                error: serde_json::from_value(json!({ "code": 50035, "message": message })).unwrap(),
+            };
+            return Err(HttpError::UnsuccessfulRequest(response).into())
+        }
+
+        self.edits.lock().unwrap().push((field.to_owned(), value));
+
+        Ok(())
+    }
+
+    fn guild_name(&self, guild_id: GuildId) -> Option<String> {
+        self.guilds.iter().find(|guild| guild.id == guild_id).map(|guild| guild.name.clone())
+    }
+
+    fn guilds(&self) -> Vec<Guild> {
+        self.guilds.clone()
+    }
+
+    fn cache_size(&self) -> CacheSize {
+        self.cache_size
+    }
+
+    async fn leave(&self, guild_id: GuildId) -> Result<()> {
+        self.left.lock().unwrap().push(guild_id);
+
+        Ok(())
+    }
+
+    async fn shards(&self) -> Vec<Shard> {
+        self.shards.clone()
+    }
+
+    fn shard_total(&self) -> u64 {
+        self.shard_total
+    }
+
+    async fn restart_shard(&self, id: u64) {
+        self.restarted.lock().unwrap().push(id);
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use async_tungstenite::tokio::accept_async;
+use async_tungstenite::tungstenite::Message;
+use poise::futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const HEARTBEAT_INTERVAL: u64 = 45000;
+
+struct State {
+    ready: Value,
+    frames: Mutex<Vec<Value>>,
+    session: Mutex<Option<UnboundedSender<Value>>>,
+}
+
+/// Fake Discord gateway speaking plain JSON over an unencrypted WebSocket.
+///
+/// A session answers `IDENTIFY` with the given `READY` payload and
+/// heartbeats with acks, every other frame the bot sends is recorded for
+/// [`Gateway::wait`].
+#[derive(Clone)]
+pub struct Gateway {
+    address: SocketAddr,
+    state: Arc<State>,
+}
+
+impl Gateway {
+    pub async fn start(ready: Value) -> Gateway {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let state = Arc::new(State {
+            ready,
+            frames: Mutex::new(Vec::new()),
+            session: Mutex::new(None),
+        });
+
+        tokio::spawn({
+            let state = state.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(session(stream, state.clone()));
+                }
+            }
+        });
+
+        Gateway { address, state }
+    }
+
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.address)
+    }
+
+    /// Sends a `DISPATCH` of `kind` once a session is identified.
+    pub async fn dispatch(&self, kind: &str, data: Value) {
+        let deadline = Instant::now() + TIMEOUT;
+        let frame = json!({ "op": 0, "t": kind, "d": data });
+
+        loop {
+            if let Some(session) = self.state.session.lock().unwrap().as_ref() {
+                if session.send(frame.clone()).is_ok() {
+                    return
+                }
+            }
+
+            if Instant::now() > deadline {
+                panic!("no session identified to dispatch {kind} to");
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Takes the data of the oldest recorded frame with `op`, waiting for it
+    /// to arrive and panicking once it didn't in time.
+    pub async fn wait(&self, op: u64) -> Value {
+        let deadline = Instant::now() + TIMEOUT;
+
+        loop {
+            {
+                let mut frames = self.state.frames.lock().unwrap();
+                if let Some(index) = frames.iter().position(|frame| frame["op"] == op) {
+                    return frames.remove(index)["d"].take()
+                }
+            }
+
+            if Instant::now() > deadline {
+                panic!("no frame with op {op} arrived");
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
+
+async fn session(stream: TcpStream, state: Arc<State>) {
+    let Ok(mut socket) = accept_async(stream).await else {
+        return
+    };
+
+    let hello = json!({ "op": 10, "d": { "heartbeat_interval": HEARTBEAT_INTERVAL } });
+    if socket.send(Message::Text(hello.to_string())).await.is_err() {
+        return
+    }
+
+    let (sender, mut receiver) = mpsc::unbounded_channel::<Value>();
+    let mut sequence = 0;
+
+    loop {
+        let mut outgoing = tokio::select! {
+            frame = socket.next() => {
+                let frame = match frame {
+                    Some(Ok(Message::Text(text))) => serde_json::from_str::<Value>(&text).unwrap_or_default(),
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+
+                let outgoing = match frame["op"].as_u64() {
+                    Some(1) => Some(json!({ "op": 11 })),
+                    Some(2) => {
+                        state.session.lock().unwrap().replace(sender.clone());
+                        Some(json!({ "op": 0, "t": "READY", "d": state.ready }))
+                    }
+                    _ => None,
+                };
+                state.frames.lock().unwrap().push(frame);
+
+                match outgoing {
+                    Some(outgoing) => outgoing,
+                    None => continue,
+                }
+            }
+            Some(frame) = receiver.recv() => frame,
+        };
+
+        if outgoing["op"] == 0 {
+            sequence += 1;
+            outgoing["s"] = json!(sequence);
+        }
+
+        if socket.send(Message::Text(outgoing.to_string())).await.is_err() {
+            break
+        }
+    }
+}
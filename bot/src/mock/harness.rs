@@ -0,0 +1,147 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use hyper::Method;
+use poise::Framework;
+use poise::serenity_prelude::{ClientBuilder, HttpBuilder, UserId};
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+use crate::{base, core, metrics, server, storage};
+use crate::base::{Config, Data, Source};
+use crate::helper::Error;
+use crate::log::Logger;
+use crate::mock::{payload, Gateway, Rest};
+
+pub const BOT: u64 = 100;
+pub const APPLICATION: u64 = 100;
+pub const OWNER: u64 = 200;
+pub const MEMBER: u64 = 300;
+pub const GUILD: u64 = 400;
+pub const CHANNEL: u64 = 500;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The bot wired exactly like `run()` does, except that HTTP goes to a
+/// [`Rest`] fake, the gateway is a [`Gateway`] fake and storage is in memory.
+///
+/// [`OWNER`] is a configured owner, [`MEMBER`] has no permissions at all and
+/// both talk in [`CHANNEL`] of [`GUILD`] with the `!` prefix.
+pub struct Harness {
+    pub rest: Rest,
+    pub gateway: Gateway,
+    pub framework: Arc<Framework<Data, Error>>,
+    ids: AtomicU64,
+    client: JoinHandle<()>,
+}
+
+impl Harness {
+    pub async fn start() -> Harness {
+        Harness::with_config(|_| ()).await
+    }
+
+    /// Starts with the defaults of the harness adjusted by `configure`,
+    /// returning once the `Ready` went through setup.
+    pub async fn with_config(configure: impl FnOnce(&mut Config)) -> Harness {
+        let mut config = Config::default();
+        config.core.token = "mock".to_owned();
+        config.core.prefixes = vec!["!".to_owned()];
+        config.core.owners = vec![UserId(OWNER)];
+        config.storage.engine = storage::Engine::Memory;
+        configure(&mut config);
+
+        let bot = payload::user(BOT, "bot", true);
+        let gateway = Gateway::start(payload::ready(bot.clone(), APPLICATION, &[GUILD])).await;
+        let rest = Rest::start(gateway.url(), bot).await;
+
+        let shards = config.core.shards;
+        let storage = storage::Storage::open(&config.storage).unwrap();
+        storage.migrate().await.unwrap();
+
+        let source = Source { path: "mock.toml".into(), explicit: false };
+        let modules = Arc::new(base::registry().enabled(&config));
+        let reloader = base::Reloader::new(source, config, Arc::new(Logger::detached()));
+        let shared = base::Shared {
+            reloader,
+            metrics: Arc::new(metrics::Metrics::new().unwrap()),
+            health: Arc::new(server::Health::default()),
+            storage,
+            shutdown: base::Shutdown::default(),
+            modules,
+        };
+
+        let http = HttpBuilder::new("mock")
+            .proxy(rest.url())
+            .unwrap()
+            .ratelimiter_disabled(true)
+            .build();
+        let options = core::options(shared.modules.commands(), HashSet::new());
+        let client = ClientBuilder::new_with_http(http, core::intents());
+        let framework = core::framework(client, options, shared).await.unwrap();
+
+        let client = tokio::spawn({
+            let framework = framework.clone();
+            async move {
+                core::start(framework, shards).await.unwrap();
+            }
+        });
+
+        if tokio::time::timeout(TIMEOUT, framework.user_data()).await.is_err() {
+            panic!("setup did not run, calls: {:?}", rest.calls());
+        }
+
+        Harness { rest, gateway, framework, ids: AtomicU64::new(1), client }
+    }
+
+    pub async fn data(&self) -> &Data {
+        self.framework.user_data().await
+    }
+
+    /// Sends another `READY`, as a shard connecting after the first one would.
+    pub async fn ready(&self) {
+        let bot = payload::user(BOT, "bot", true);
+
+        self.gateway.dispatch("READY", payload::ready(bot, APPLICATION, &[GUILD])).await;
+    }
+
+    /// Sends `content` from `author` as a `MESSAGE_CREATE` in [`CHANNEL`].
+    pub async fn send(&self, author: u64, content: &str) {
+        let id = self.ids.fetch_add(1, Ordering::Relaxed);
+        let author = payload::user(author, &format!("user{author}"), false);
+        let message = payload::message(id, CHANNEL, Some(GUILD), author, content);
+
+        self.gateway.dispatch("MESSAGE_CREATE", message).await;
+    }
+
+    /// Sends `content` as [`OWNER`] and returns the first reply.
+    pub async fn command(&self, content: &str) -> String {
+        self.send(OWNER, content).await;
+        self.reply().await
+    }
+
+    /// Takes the oldest message the bot sent to [`CHANNEL`].
+    pub async fn reply(&self) -> String {
+        let call = self.rest.wait(Method::POST, &format!("/channels/{CHANNEL}/messages")).await;
+
+        call.body["content"].as_str().unwrap_or_default().to_owned()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        self.client.abort();
+    }
+}
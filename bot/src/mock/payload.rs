@@ -0,0 +1,66 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde_json::{json, Value};
+
+pub fn user(id: u64, name: &str, bot: bool) -> Value {
+    json!({
+        "id": id.to_string(),
+        "username": name,
+        "discriminator": "0001",
+        "avatar": null,
+        "bot": bot,
+    })
+}
+
+/// A message in the shape of `MESSAGE_CREATE` and the message endpoints.
+pub fn message(id: u64, channel_id: u64, guild_id: Option<u64>, author: Value, content: &str) -> Value {
+    json!({
+        "id": id.to_string(),
+        "channel_id": channel_id.to_string(),
+        "guild_id": guild_id.map(|guild_id| guild_id.to_string()),
+        "author": author,
+        "content": content,
+        "timestamp": "2023-01-01T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "pinned": false,
+        "type": 0,
+    })
+}
+
+pub fn ready(user: Value, application_id: u64, guild_ids: &[u64]) -> Value {
+    let mut current_user = user;
+    current_user["mfa_enabled"] = json!(false);
+    current_user["verified"] = json!(true);
+
+    let guilds = guild_ids
+        .iter()
+        .map(|guild_id| json!({ "id": guild_id.to_string(), "unavailable": true }))
+        .collect::<Vec<Value>>();
+
+    json!({
+        "v": 10,
+        "user": current_user,
+        "guilds": guilds,
+        "session_id": "mock",
+        "shard": [0, 1],
+        "application": { "id": application_id.to_string(), "flags": 0 },
+    })
+}
@@ -0,0 +1,176 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use serde_json::{json, Value};
+use tokio::time::{Duration, Instant};
+use crate::mock::payload;
+
+const API_PREFIX: &str = "/api/v10";
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A request the bot made, with the `/api/v10` prefix stripped from `path`.
+#[derive(Clone, Debug)]
+pub struct Call {
+    pub method: Method,
+    pub path: String,
+    pub body: Value,
+}
+
+/// A canned response replacing the default one for an exact method and path.
+struct Route {
+    method: Method,
+    path: String,
+    status: StatusCode,
+    body: Value,
+}
+
+struct State {
+    gateway: String,
+    bot: Value,
+    calls: Mutex<Vec<Call>>,
+    routes: Mutex<Vec<Route>>,
+    ids: AtomicU64,
+}
+
+/// Fake Discord REST API, reached through serenity's HTTP proxy setting.
+///
+/// Every request is recorded for [`Rest::wait`], unknown routes answer 404
+/// like Discord does so a test notices calls it did not expect.
+#[derive(Clone)]
+pub struct Rest {
+    address: SocketAddr,
+    state: Arc<State>,
+}
+
+impl Rest {
+    pub async fn start(gateway: String, bot: Value) -> Rest {
+        let state = Arc::new(State {
+            gateway,
+            bot,
+            calls: Mutex::new(Vec::new()),
+            routes: Mutex::new(Vec::new()),
+            ids: AtomicU64::new(1),
+        });
+
+        let service = make_service_fn({
+            let state = state.clone();
+            move |_| {
+                let state = state.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| serve(request, state.clone())))
+                }
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(service);
+        let address = server.local_addr();
+        tokio::spawn(server);
+
+        Rest { address, state }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    pub fn respond(&self, method: Method, path: &str, status: StatusCode, body: Value) {
+        let route = Route { method, path: path.to_owned(), status, body };
+        self.state.routes.lock().unwrap().push(route);
+    }
+
+    /// Takes the oldest recorded call to `method` and `path`, waiting for it
+    /// to arrive and panicking once it didn't in time.
+    pub async fn wait(&self, method: Method, path: &str) -> Call {
+        let deadline = Instant::now() + TIMEOUT;
+
+        loop {
+            {
+                let mut calls = self.state.calls.lock().unwrap();
+                if let Some(index) = calls.iter().position(|call| call.method == method && call.path == path) {
+                    return calls.remove(index)
+                }
+            }
+
+            if Instant::now() > deadline {
+                let calls = self.calls();
+                panic!("no {method} {path} call arrived, calls left: {calls:?}");
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    pub fn calls(&self) -> Vec<Call> {
+        self.state.calls.lock().unwrap().clone()
+    }
+}
+
+async fn serve(request: Request<Body>, state: Arc<State>) -> Result<Response<Body>, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path();
+    let path = path.strip_prefix(API_PREFIX).unwrap_or(path).to_owned();
+
+    let bytes = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default();
+    let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+
+    let call = Call { method, path, body };
+    let (status, body) = respond(&call, &state);
+    state.calls.lock().unwrap().push(call);
+
+    let response = Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    Ok(response)
+}
+
+fn respond(call: &Call, state: &State) -> (StatusCode, Value) {
+    let routes = state.routes.lock().unwrap();
+    if let Some(route) = routes.iter().rev().find(|route| route.method == call.method && route.path == call.path) {
+        return (route.status, route.body.clone())
+    }
+
+    let segments = call.path.trim_matches('/').split('/').collect::<Vec<&str>>();
+
+    match (&call.method, segments.as_slice()) {
+        (&Method::GET, ["gateway"]) => (StatusCode::OK, json!({ "url": state.gateway })),
+        (&Method::GET, ["gateway", "bot"]) => {
+            let limit = json!({ "total": 1000, "remaining": 1000, "reset_after": 0, "max_concurrency": 1 });
+            (StatusCode::OK, json!({ "url": state.gateway, "shards": 1, "session_start_limit": limit }))
+        }
+        (&Method::POST, ["channels", channel_id, "messages"]) => {
+            let id = state.ids.fetch_add(1, Ordering::Relaxed);
+            let channel_id = channel_id.parse().unwrap_or_default();
+            let content = call.body["content"].as_str().unwrap_or_default();
+            (StatusCode::OK, payload::message(id, channel_id, None, state.bot.clone(), content))
+        }
+        (&Method::PATCH, ["channels", channel_id, "messages", message_id]) => {
+            let id = message_id.parse().unwrap_or_default();
+            let channel_id = channel_id.parse().unwrap_or_default();
+            let content = call.body["content"].as_str().unwrap_or_default();
+            (StatusCode::OK, payload::message(id, channel_id, None, state.bot.clone(), content))
+        }
+        (&Method::GET | &Method::PUT, ["applications", _, "commands"])
+        | (&Method::GET | &Method::PUT, ["applications", _, "guilds", _, "commands"]) => (StatusCode::OK, json!([])),
+        _ => (StatusCode::NOT_FOUND, json!({ "message": "404: Not Found", "code": 0 })),
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use poise::serenity_prelude::{Command, CreateApplicationCommands, GuildId, Http};
+use serde_json::{Map, Value};
+use crate::base::Data;
+use crate::core::{self, Level};
+use crate::helper::{Error, Result};
+
+const COMPARED: [&str; 5] = ["type", "description", "options", "default_member_permissions", "dm_permission"];
+
+/// Added, changed and removed command names between two registrations.
+pub struct Diff {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl Diff {
+    pub fn new(current: &[Value], desired: &[Value]) -> Self {
+        let find = |commands: &[Value], name: &str| commands
+            .iter()
+            .find(|command| command["name"] == name)
+            .map(normalize);
+
+        let mut diff = Diff { added: Vec::new(), changed: Vec::new(), removed: Vec::new() };
+
+        for command in desired {
+            let name = command["name"].as_str().unwrap_or_default().to_owned();
+
+            match find(current, &name) {
+                None => diff.added.push(name),
+                Some(existing) if existing != normalize(command) => diff.changed.push(name),
+                Some(_) => (),
+            }
+        }
+
+        for command in current {
+            let name = command["name"].as_str().unwrap_or_default();
+
+            if find(desired, name).is_none() {
+                diff.removed.push(name.to_owned());
+            }
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    pub fn describe(&self) -> String {
+        [("+", &self.added), ("~", &self.changed), ("-", &self.removed)]
+            .iter()
+            .flat_map(|(sign, names)| names.iter().map(move |name| format!("{sign} {name}")))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// Commands for owners and bot admins are only ever registered in the
+/// configured admin guild.
+pub fn commands(commands: &[poise::Command<Data, Error>], admin_guild: bool) -> Vec<Value> {
+    let mut builder = CreateApplicationCommands::default();
+
+    commands
+        .iter()
+        .filter(|command| admin_guild || core::required(command) < Level::BotAdmin)
+        .filter_map(poise::Command::create_as_slash_command)
+        .for_each(|command| {
+            builder.add_application_command(command);
+        });
+
+    builder.0
+}
+
+pub async fn current(http: &Http, guild_id: Option<GuildId>) -> Result<Vec<Value>> {
+    let commands = match guild_id {
+        Some(guild_id) => http.get_guild_application_commands(guild_id.0).await?,
+        None => http.get_global_application_commands().await?,
+    };
+
+    let commands = commands
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<serde_json::Result<Vec<Value>>>()?;
+
+    Ok(commands)
+}
+
+pub async fn apply(http: &Http, guild_id: Option<GuildId>, commands: Vec<Value>) -> Result<()> {
+    let commands = CreateApplicationCommands(commands);
+
+    match guild_id {
+        Some(guild_id) => {
+            guild_id.set_application_commands(http, |builder| {
+                *builder = commands;
+                builder
+            }).await?;
+        }
+        None => {
+            Command::set_global_application_commands(http, |builder| {
+                *builder = commands;
+                builder
+            }).await?;
+        }
+    };
+
+    Ok(())
+}
+
+/// FNV-1a over the serialized commands, stable across builds and platforms.
+pub fn fingerprint(commands: &[Value]) -> String {
+    let serialized = Value::Array(commands.to_vec()).to_string();
+    let hash = serialized.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+
+    format!("{hash:016x}")
+}
+
+fn normalize(command: &Value) -> Value {
+    let mut projection = Map::new();
+
+    for key in COMPARED {
+        let value = match (key, &command[key]) {
+            // chat input commands are built without a type, Discord fills it in
+            ("type", Value::Null) => Value::from(1),
+            ("default_member_permissions", Value::Number(number)) => Value::String(number.to_string()),
+            ("dm_permission", Value::Null) => Value::Bool(true),
+            (_, value) => value.clone(),
+        };
+        projection.insert(key.to_owned(), value);
+    }
+
+    prune(Value::Object(projection))
+}
+
+/// Drops the fields Discord leaves out when they hold their default value.
+fn prune(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let map = map
+                .into_iter()
+                .map(|(key, value)| (key, prune(value)))
+                .filter(|(_, value)| !is_default(value))
+                .collect();
+            Value::Object(map)
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(prune).collect()),
+        value => value,
+    }
+}
+
+fn is_default(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Bool(false) => true,
+        Value::Array(values) => values.is_empty(),
+        Value::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
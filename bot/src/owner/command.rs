@@ -12,148 +12,853 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use poise::serenity_prelude::{Activity, GuildId, OnlineStatus};
+use std::time::Instant;
+use poise::serenity_prelude::{Attachment, GuildId, User};
 use tokio::time::Duration;
-use crate::helper::{Context, Result};
-use crate::owner::{ActivityType, StatusType};
+use serde_json::Value;
+use time::{OffsetDateTime, Time};
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use crate::base;
+use crate::core::{self, Level, BOT_ADMINS};
+use crate::helper::{self, ArcMut, Context, Result};
+use crate::owner;
+use crate::owner::application::Diff;
+use crate::owner::profile::{self, Image};
+use crate::owner::{ActivityType, CacheSize, Discord, Guild, Presence, Rotation, Schedule, Shard, StatusType};
+use crate::owner::{PRESENCE, ROTATION, SCHEDULE};
+use crate::storage::{Scope, Storage};
 
+const MAX_SCHEDULE: time::Duration = time::Duration::days(30);
+const GUILDS_PER_PAGE: usize = 20;
+
+/// Bot management for owners and bot admins
 #[poise::command(
-    prefix_command, owners_only, guild_only,
+    prefix_command, slash_command, custom_data = "Level::BotAdmin", guild_only,
+    default_member_permissions = "ADMINISTRATOR",
     rename = "owner",
-    subcommands("avatar", "presence", "register", "shutdown"),
+    subcommands(
+        "avatar", "banner", "username", "presence", "register", "unregister", "reload",
+        "shutdown", "restart", "status", "shards", "guilds", "leave", "admin",
+    ),
 )]
 pub async fn group(_: Context<'_>) -> Result<()> {
     Ok(())
 }
 
-#[poise::command(prefix_command, owners_only, guild_only)]
-pub async fn avatar(context: Context<'_>) -> Result<()> {
-    let Context::Prefix(prefix_context) = context else { unreachable!() };
-    let attachments = &prefix_context.msg.attachments;
+/// Set the bot avatar from an attachment or url
+#[poise::command(
+    prefix_command, slash_command, custom_data = "Level::Owner", guild_only,
+    subcommands("avatar_set", "avatar_reset"),
+)]
+pub async fn avatar(
+    context: Context<'_>,
+    #[description = "Image to upload"] attachment: Option<Attachment>,
+    #[description = "Crop the image to a square"] #[flag] square: bool,
+    #[description = "Link to the image"] url: Option<String>,
+) -> Result<()> {
+    set_image(&context, "avatar", attachment, url, square).await
+}
+
+/// Set the bot avatar from an attachment or url
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only, rename = "set")]
+pub async fn avatar_set(
+    context: Context<'_>,
+    #[description = "Image to upload"] attachment: Option<Attachment>,
+    #[description = "Crop the image to a square"] #[flag] square: bool,
+    #[description = "Link to the image"] url: Option<String>,
+) -> Result<()> {
+    set_image(&context, "avatar", attachment, url, square).await
+}
+
+/// Reset the bot avatar to the default one
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only, rename = "reset")]
+pub async fn avatar_reset(context: Context<'_>) -> Result<()> {
+    reset_image(&context, "avatar").await
+}
+
+/// Set the bot banner from an attachment or url
+#[poise::command(
+    prefix_command, slash_command, custom_data = "Level::Owner", guild_only,
+    subcommands("banner_set", "banner_reset"),
+)]
+pub async fn banner(
+    context: Context<'_>,
+    #[description = "Image to upload"] attachment: Option<Attachment>,
+    #[description = "Link to the image"] url: Option<String>,
+) -> Result<()> {
+    set_image(&context, "banner", attachment, url, false).await
+}
+
+/// Set the bot banner from an attachment or url
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only, rename = "set")]
+pub async fn banner_set(
+    context: Context<'_>,
+    #[description = "Image to upload"] attachment: Option<Attachment>,
+    #[description = "Link to the image"] url: Option<String>,
+) -> Result<()> {
+    set_image(&context, "banner", attachment, url, false).await
+}
+
+/// Remove the bot banner
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only, rename = "reset")]
+pub async fn banner_reset(context: Context<'_>) -> Result<()> {
+    reset_image(&context, "banner").await
+}
+
+/// Validates an attached or linked image and uploads it as `field`, cut to a
+/// square and scaled down first with `square`.
+pub async fn set_image(
+    discord: &impl Discord,
+    field: &str,
+    attachment: Option<Attachment>,
+    url: Option<String>,
+    square: bool,
+) -> Result<()> {
+    let bytes = match (attachment, url) {
+        (Some(attachment), _) if attachment.size as usize > profile::MAX_SIZE => {
+            let size = attachment.size / 1024;
+            let limit = profile::MAX_SIZE / 1024;
+            discord.reply(format!("image is {size} KiB, the limit is {limit} KiB!")).await?;
+            return Ok(())
+        }
+        (Some(attachment), _) => discord.attachment(&attachment).await?,
+        (None, Some(url)) => match discord.download(&url).await {
+            Ok(bytes) => bytes,
+            Err(reason) => {
+                discord.reply(reason).await?;
+                return Ok(())
+            }
+        },
+        (None, None) => {
+            discord.reply("attachment or url is required!").await?;
+            return Ok(())
+        }
+    };
+
+    let mut image = match Image::new(bytes) {
+        Ok(image) => image,
+        Err(reason) => {
+            discord.reply(reason).await?;
+            return Ok(())
+        }
+    };
+
+    let mut response = format!("setting {field}...");
+    if let Some((width, height)) = image.dimensions().filter(|_| field == "avatar") {
+        let fits = width == height && width <= profile::AVATAR_SIZE;
+        match (square && !fits).then(|| image.crop_square()).flatten() {
+            Some(cropped) => {
+                let size = cropped.dimensions().map_or(0, |(size, _)| size);
+                response.push_str(&format!("\ncropped the {width}x{height} image to {size}x{size}!"));
+                image = cropped;
+            }
+            None if square && !fits => response.push_str(&format!(
+                "\ncouldn't crop the {width}x{height} image, uploading it as is!"
+            )),
+            None if width != height => {
+                response.push_str(&format!("\nimage is {width}x{height}, discord will crop it to a square!"))
+            }
+            None => (),
+        }
+    }
+    discord.reply(response).await?;
+
+    edit_profile(discord, field, Value::String(image.data_uri())).await?;
 
-    if attachments.is_empty() {
-        context.say("attachment is required!").await?;
+    Ok(())
+}
+
+pub async fn reset_image(discord: &impl Discord, field: &str) -> Result<()> {
+    discord.reply(format!("resetting {field}...")).await?;
+    edit_profile(discord, field, Value::Null).await?;
+
+    Ok(())
+}
+
+/// Rename the bot, discord allows two changes per hour
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only)]
+pub async fn username(
+    context: Context<'_>,
+    #[description = "New username"] #[rest] name: String,
+) -> Result<()> {
+    rename(&context, &context.data().storage, &name).await
+}
+
+pub async fn rename(discord: &impl Discord, storage: &Storage, name: &str) -> Result<()> {
+    let name = name.trim();
+
+    if !(2..=32).contains(&name.chars().count()) {
+        discord.reply("\"name\" parameter must be in between 2 and 32 characters!").await?;
         return Ok(())
     }
 
-    let serenity_context = context.serenity_context();
-    let base64 = attachments[0].download().await.map(base64::encode)?;
-    let avatar = format!("data:image/png;base64,{base64}");
+    if let Some(seconds) = profile::rename_cooldown(storage).await? {
+        let remaining = helper::format_duration(Duration::from_secs(seconds));
+        discord.reply(format!("discord only allows 2 username changes per hour, try again in {remaining}!")).await?;
+        return Ok(())
+    }
 
-    context.say("setting avatar...").await?;
-    serenity_context.cache
-        .current_user()
-        .edit(&serenity_context, |profile| profile.avatar(Some(&avatar))).await?;
+    discord.reply(format!("renaming to {name}...")).await?;
+    if edit_profile(discord, "username", Value::String(name.to_owned())).await? {
+        profile::record_rename(storage).await?;
+    }
 
     Ok(())
 }
 
-#[poise::command(prefix_command, owners_only, guild_only)]
+/// Edits the profile, returning whether discord applied the change and
+/// telling the user why when it was rejected.
+async fn edit_profile(discord: &impl Discord, field: &str, value: Value) -> Result<bool> {
+    let Err(error) = discord.edit_profile(field, value).await else {
+        return Ok(true)
+    };
+
+    let Some(reason) = profile::rejection(&error) else {
+        return Err(error.into())
+    };
+    discord.reply(format!("discord rejected the {field}: {reason}!")).await?;
+
+    Ok(false)
+}
+
+/// Set the bot presence
+#[poise::command(
+    prefix_command, slash_command, custom_data = "Level::BotAdmin", guild_only,
+    subcommands("set", "rotate", "stop", "list"),
+)]
 pub async fn presence(
     context: Context<'_>,
-    status: StatusType,
-    activity: Option<ActivityType>,
-    #[rest] name: Option<String>,
-) -> Result<()> {
-    let name = name.unwrap_or_else(|| "...".to_owned());
-    let activity = activity.map(|activity| {
-        match activity {
-            ActivityType::Playing => Activity::playing(name),
-            ActivityType::Listening => Activity::listening(name),
-            ActivityType::Watching => Activity::watching(name),
-            ActivityType::Competing => Activity::competing(name),
-        }
-    });
-    let status = match status {
-        StatusType::Dnd => OnlineStatus::DoNotDisturb,
-        StatusType::Idle => OnlineStatus::Idle,
-        StatusType::Invisible => OnlineStatus::Invisible,
-        StatusType::Offline => OnlineStatus::Offline,
-        StatusType::Online => OnlineStatus::Online,
+    #[description = "Online status"] status: StatusType,
+    #[description = "Activity type"] activity: Option<ActivityType>,
+    #[description = "Activity text"] #[rest] name: Option<String>,
+) -> Result<()> {
+    let data = context.data();
+    set_presence(&context, &data.storage, &state(data)?, Presence { status, activity, name }).await
+}
+
+/// Set the bot presence
+#[poise::command(prefix_command, slash_command, custom_data = "Level::BotAdmin", guild_only)]
+pub async fn set(
+    context: Context<'_>,
+    #[description = "Online status"] status: StatusType,
+    #[description = "Activity type"] activity: Option<ActivityType>,
+    #[description = "Activity text"] #[rest] name: Option<String>,
+) -> Result<()> {
+    let data = context.data();
+    set_presence(&context, &data.storage, &state(data)?, Presence { status, activity, name }).await
+}
+
+/// Replaces any rotation with a fixed presence that survives restarts.
+pub async fn set_presence(
+    discord: &impl Discord,
+    storage: &Storage,
+    owner: &ArcMut<owner::Data>,
+    presence: Presence,
+) -> Result<()> {
+    if let Some(rotation) = owner.lock().await.rotation.take() {
+        rotation.abort();
+    }
+
+    discord.reply("setting presence...").await?;
+    discord.set_presence(&presence).await;
+    storage.remove(Scope::Global, &ROTATION).await?;
+    storage.set(Scope::Global, &PRESENCE, &presence).await?;
+
+    Ok(())
+}
+
+/// Rotate presences, entries look like "STATUS [ACTIVITY] [TEXT] | ..."
+#[poise::command(prefix_command, slash_command, custom_data = "Level::BotAdmin", guild_only)]
+pub async fn rotate(
+    context: Context<'_>,
+    #[description = "Seconds between entries"] interval: u64,
+    #[description = "Entries separated by |"] #[rest] entries: String,
+) -> Result<()> {
+    let data = context.data();
+    start_rotation(&context, &data.storage, &state(data)?, interval, &entries).await
+}
+
+/// Replaces any running rotation with one cycling through `entries`.
+pub async fn start_rotation(
+    discord: &impl Discord,
+    storage: &Storage,
+    owner: &ArcMut<owner::Data>,
+    interval: u64,
+    entries: &str,
+) -> Result<()> {
+    if !(15..=86400).contains(&interval) {
+        discord.reply("\"interval\" parameter must be in between 15 and 86400 seconds!").await?;
+        return Ok(())
+    }
+
+    let Some(entries) = entries.split('|').map(parse_presence).collect::<Option<Vec<Presence>>>() else {
+        discord.reply("entries must look like \"STATUS [ACTIVITY] [TEXT] | ...\"!").await?;
+        return Ok(())
     };
 
-    context.say("setting presence...").await?;
-    context.serenity_context().set_presence(activity, status).await;
+    let rotation = Rotation { interval, entries };
+    let mut state = owner.lock().await;
+
+    if let Some(rotation) = state.rotation.take() {
+        rotation.abort();
+    }
+
+    let response = format!("rotating {} presences every {interval} seconds...", rotation.entries.len());
+    discord.reply(response).await?;
+    storage.set(Scope::Global, &ROTATION, &rotation).await?;
+    state.rotation.replace(discord.spawn_rotation(rotation, owner));
 
     Ok(())
 }
 
-#[poise::command(prefix_command, owners_only, guild_only)]
-pub async fn register(context: Context<'_>, scope: String) -> Result<()> {
-    let serenity_context = context.serenity_context();
-    let commands = &context.framework().options().commands;
+/// Stop the presence rotation
+#[poise::command(prefix_command, slash_command, custom_data = "Level::BotAdmin", guild_only)]
+pub async fn stop(context: Context<'_>) -> Result<()> {
+    let data = context.data();
+    stop_rotation(&context, &data.storage, &data.config(), &state(data)?).await
+}
 
-    match scope.as_str() {
-        "local" => {
-            context.say("registering commands locally...").await?;
-            let guild_id = context.guild_id().unwrap();
-            poise::builtins::register_in_guild(&serenity_context, commands, guild_id).await?;
-        }
-        "global" => {
-            context.say("registering commands globally...").await?;
-            poise::builtins::register_globally(&serenity_context, commands).await?;
-        }
-        scope => {
-            let Ok(guild_id) = scope.parse::<u64>().map(GuildId) else {
-                context.say("\"scope\" parameter must be \"local\", \"global\", or GUILD_ID!").await?;
-                return Ok(())
-            };
+/// Stops the rotation and goes back to the stored or configured presence.
+pub async fn stop_rotation(
+    discord: &impl Discord,
+    storage: &Storage,
+    config: &base::Config,
+    owner: &ArcMut<owner::Data>,
+) -> Result<()> {
+    let Some(rotation) = owner.lock().await.rotation.take() else {
+        discord.reply("no presence rotation is running!").await?;
+        return Ok(())
+    };
+    rotation.abort();
 
-            let response = format!("registering commands for {guild_id}...");
-            context.say(response).await?;
-            poise::builtins::register_in_guild(&serenity_context, commands, guild_id).await?;
-        }
+    discord.reply("stopping presence rotation...").await?;
+    storage.remove(Scope::Global, &ROTATION).await?;
+
+    if let Some(presence) = Presence::stored(storage, config).await? {
+        discord.set_presence(&presence).await;
+    }
+
+    Ok(())
+}
+
+/// List the presence rotation
+#[poise::command(prefix_command, slash_command, custom_data = "Level::BotAdmin", guild_only)]
+pub async fn list(context: Context<'_>) -> Result<()> {
+    list_rotation(&context, &context.data().storage).await
+}
+
+pub async fn list_rotation(discord: &impl Discord, storage: &Storage) -> Result<()> {
+    let Some(rotation) = storage.get(Scope::Global, &ROTATION).await? else {
+        discord.reply("no presence rotation is configured!").await?;
+        return Ok(())
+    };
+
+    let mut response = format!("rotating every {} seconds:", rotation.interval);
+    for (index, presence) in rotation.entries.iter().enumerate() {
+        let line = format!("\n{}. {}", index + 1, presence.describe());
+        response.push_str(&line);
+    }
+    discord.reply(response).await?;
+
+    Ok(())
+}
+
+/// Register slash commands locally, globally or in a guild
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only)]
+pub async fn register(
+    context: Context<'_>,
+    #[description = "local, global or GUILD_ID"] scope: String,
+) -> Result<()> {
+    let admin_guild = owner::Config::of(&context.data().config()).guild;
+    register_commands(&context, admin_guild, &scope).await
+}
+
+/// Registers the commands that changed, admin commands only go to `admin_guild`.
+pub async fn register_commands(discord: &impl Discord, admin_guild: Option<GuildId>, scope: &str) -> Result<()> {
+    let Some(guild_id) = parse_scope(discord, scope) else {
+        discord.reply("\"scope\" parameter must be \"local\", \"global\", or GUILD_ID!").await?;
+        return Ok(())
+    };
+
+    let desired = discord.commands(guild_id.is_some() && guild_id == admin_guild);
+    let current = discord.registered_commands(guild_id).await?;
+    let diff = Diff::new(&current, &desired);
+
+    if diff.is_empty() {
+        discord.reply("registered commands are already up to date!").await?;
+        return Ok(())
+    }
+
+    let response = match guild_id {
+        Some(guild_id) => format!("registering commands for {guild_id}...\n```diff\n{}\n```", diff.describe()),
+        None => format!("registering commands globally...\n```diff\n{}\n```", diff.describe()),
+    };
+    discord.reply(response).await?;
+    discord.register_commands(guild_id, desired).await?;
+
+    Ok(())
+}
+
+/// Remove slash commands locally, globally or from a guild
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only)]
+pub async fn unregister(
+    context: Context<'_>,
+    #[description = "local, global or GUILD_ID"] scope: String,
+) -> Result<()> {
+    unregister_commands(&context, &scope).await
+}
+
+pub async fn unregister_commands(discord: &impl Discord, scope: &str) -> Result<()> {
+    let Some(guild_id) = parse_scope(discord, scope) else {
+        discord.reply("\"scope\" parameter must be \"local\", \"global\", or GUILD_ID!").await?;
+        return Ok(())
+    };
+
+    let response = match guild_id {
+        Some(guild_id) => format!("unregistering commands for {guild_id}..."),
+        None => "unregistering commands globally...".to_owned(),
+    };
+    discord.reply(response).await?;
+    discord.register_commands(guild_id, Vec::new()).await?;
+
+    Ok(())
+}
+
+/// Reload the config file
+#[poise::command(prefix_command, slash_command, custom_data = "Level::BotAdmin", guild_only)]
+pub async fn reload(context: Context<'_>) -> Result<()> {
+    reload_config(&context, &context.data().reloader).await
+}
+
+pub async fn reload_config(discord: &impl Discord, reloader: &base::Reloader) -> Result<()> {
+    discord.reply("reloading config...").await?;
+
+    if let Err(error) = reloader.reload() {
+        let response = format!("error reloading config, keeping previous one!\n{error}");
+        discord.reply(response).await?;
     }
 
     Ok(())
 }
 
-#[poise::command(prefix_command, owners_only, guild_only)]
-pub async fn shutdown(context: Context<'_>, after: Option<u64>) -> Result<()> {
-    let framework = context.framework();
-    let owner = framework.user_data().await.owner();
+/// Shut down now or at a later time
+#[poise::command(
+    prefix_command, slash_command, custom_data = "Level::Owner", guild_only,
+    subcommands("schedule", "pending", "cancel"),
+)]
+pub async fn shutdown(
+    context: Context<'_>,
+    #[description = "Delay like 2h30m, UTC time like 21:00 or RFC3339"] when: Option<String>,
+) -> Result<()> {
+    let data = context.data();
+    schedule_shutdown(&context, &data.storage, &state(data)?, &data.shutdown, when.as_deref(), false).await
+}
+
+/// Shut down now or at a later time
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only)]
+pub async fn schedule(
+    context: Context<'_>,
+    #[description = "Delay like 2h30m, UTC time like 21:00 or RFC3339"] when: Option<String>,
+) -> Result<()> {
+    let data = context.data();
+    schedule_shutdown(&context, &data.storage, &state(data)?, &data.shutdown, when.as_deref(), false).await
+}
+
+/// Restart now or at a later time
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only)]
+pub async fn restart(
+    context: Context<'_>,
+    #[description = "Delay like 2h30m, UTC time like 21:00 or RFC3339"] when: Option<String>,
+) -> Result<()> {
+    let data = context.data();
+    schedule_shutdown(&context, &data.storage, &state(data)?, &data.shutdown, when.as_deref(), true).await
+}
+
+/// Replaces any pending schedule, shutting down right away without `when`.
+pub async fn schedule_shutdown(
+    discord: &impl Discord,
+    storage: &Storage,
+    owner: &ArcMut<owner::Data>,
+    shutdown: &base::Shutdown,
+    when: Option<&str>,
+    restart: bool,
+) -> Result<()> {
+    let now = OffsetDateTime::now_utc();
+    let at = match when {
+        Some(when) => match parse_when(when, now) {
+            Some(at) if at > now && at - now <= MAX_SCHEDULE => Some(at),
+            Some(_) => {
+                discord.reply("\"when\" parameter must be in the next 30 days!").await?;
+                return Ok(())
+            }
+            None => {
+                let response = "\"when\" parameter must be a duration like \"90s\" or \"2h30m\", \
+                    a UTC time like \"21:00\" or a timestamp like \"2030-01-01T21:00:00Z\"!";
+                discord.reply(response).await?;
+                return Ok(())
+            }
+        },
+        None => None,
+    };
+
     let mut owner = owner.lock().await;
     let mut response = String::new();
 
     if let Some(shutdown) = owner.shutdown.take() {
-        response.push_str("aborting existing shutdown task!");
+        response.push_str("aborting existing scheduled shutdown!\n");
+        shutdown.abort();
+    }
+    storage.remove(Scope::Global, &SCHEDULE).await?;
+
+    let Some(at) = at else {
+        match restart {
+            true => response.push_str("restarting..."),
+            false => response.push_str("shutting down..."),
+        };
+        discord.reply(response).await?;
+
+        match restart {
+            true => shutdown.restart(),
+            false => shutdown.trigger(),
+        };
+
+        return Ok(())
+    };
+
+    let schedule = Schedule { at: at.unix_timestamp(), restart };
+    storage.set(Scope::Global, &SCHEDULE, &schedule).await?;
+
+    response.push_str(&format!("scheduled {}!", schedule.describe()));
+    discord.reply(response).await?;
+    owner.shutdown.replace(discord.spawn_schedule(schedule));
+
+    Ok(())
+}
+
+/// Show the scheduled shutdown or restart
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only, rename = "status")]
+pub async fn pending(context: Context<'_>) -> Result<()> {
+    show_schedule(&context, &context.data().storage).await
+}
+
+pub async fn show_schedule(discord: &impl Discord, storage: &Storage) -> Result<()> {
+    let response = match storage.get(Scope::Global, &SCHEDULE).await? {
+        Some(schedule) => format!("{}!", schedule.describe()),
+        None => "no shutdown is scheduled!".to_owned(),
+    };
+    discord.reply(response).await?;
+
+    Ok(())
+}
+
+/// Cancel the scheduled shutdown or restart
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only)]
+pub async fn cancel(context: Context<'_>) -> Result<()> {
+    let data = context.data();
+    cancel_schedule(&context, &data.storage, &state(data)?).await
+}
+
+pub async fn cancel_schedule(discord: &impl Discord, storage: &Storage, owner: &ArcMut<owner::Data>) -> Result<()> {
+    let Some(schedule) = storage.get(Scope::Global, &SCHEDULE).await? else {
+        discord.reply("no shutdown is scheduled!").await?;
+        return Ok(())
+    };
+
+    if let Some(shutdown) = owner.lock().await.shutdown.take() {
         shutdown.abort();
     }
+    storage.remove(Scope::Global, &SCHEDULE).await?;
+
+    discord.reply(format!("cancelled scheduled {}!", schedule.action())).await?;
+
+    Ok(())
+}
+
+/// Show uptime, version, memory, cache and shard count
+#[poise::command(prefix_command, slash_command, custom_data = "Level::BotAdmin", guild_only)]
+pub async fn status(context: Context<'_>) -> Result<()> {
+    let data = context.data();
+    show_status(&context, data.started, data.shutdown.active()).await
+}
+
+/// `active` is the number of commands and [`base::Shutdown::spawn`] tasks that
+/// shutdown waits on, tokio has no stable count of every runtime task.
+pub async fn show_status(discord: &impl Discord, started: Instant, active: usize) -> Result<()> {
+    let CacheSize { guilds, channels, users } = discord.cache_size();
+
+    let memory = resident_memory()
+        .map_or_else(|| "unknown".to_owned(), |bytes| format!("{:.1} MiB", bytes as f64 / 1048576.0));
+
+    let shards = discord.shards().await;
+    let mut response = format!(
+        "kanae v{} ({})\nuptime: {}\nmemory: {memory}\nawaited on shutdown: {active} commands and tasks\ncache: {guilds} guilds, {channels} channels, {users} users\nshards: {} of {}",
+        env!("CARGO_PKG_VERSION"),
+        env!("GIT_HASH"),
+        helper::format_duration(started.elapsed()),
+        shards.len(),
+        discord.shard_total(),
+    );
+
+    for shard in &shards {
+        response.push_str(&format!("\n{}", describe_shard(shard)));
+    }
+
+    discord.reply(format!("```\n{response}\n```")).await?;
+
+    Ok(())
+}
+
+/// Show the status, latency and guild count of each shard
+#[poise::command(
+    prefix_command, slash_command, custom_data = "Level::BotAdmin", guild_only,
+    subcommands("shards_list", "shards_restart"),
+)]
+pub async fn shards(context: Context<'_>) -> Result<()> {
+    list_shards(&context).await
+}
+
+/// Show the status, latency and guild count of each shard
+#[poise::command(prefix_command, slash_command, custom_data = "Level::BotAdmin", guild_only, rename = "list")]
+pub async fn shards_list(context: Context<'_>) -> Result<()> {
+    list_shards(&context).await
+}
+
+pub async fn list_shards(discord: &impl Discord) -> Result<()> {
+    let shards = discord.shards().await;
+    let mut response = format!("running {} of {} shards", shards.len(), discord.shard_total());
+
+    for shard in &shards {
+        response.push_str(&format!("\n{} {} guilds", describe_shard(shard), shard.guilds));
+    }
+
+    discord.reply(format!("```\n{response}\n```")).await?;
+
+    Ok(())
+}
+
+/// Reconnect a shard run by this process
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only, rename = "restart")]
+pub async fn shards_restart(context: Context<'_>, #[description = "SHARD_ID"] shard: String) -> Result<()> {
+    restart_shard(&context, &shard).await
+}
+
+pub async fn restart_shard(discord: &impl Discord, shard: &str) -> Result<()> {
+    let Ok(id) = shard.parse::<u64>() else {
+        discord.reply("\"shard\" parameter must be SHARD_ID!").await?;
+        return Ok(())
+    };
+
+    if !discord.shards().await.iter().any(|shard| shard.id == id) {
+        discord.reply(format!("shard #{id} is not run by this process!")).await?;
+        return Ok(())
+    }
+
+    discord.reply(format!("restarting shard #{id}...")).await?;
+    discord.restart_shard(id).await;
+
+    Ok(())
+}
+
+/// List the guilds the bot is in
+#[poise::command(prefix_command, slash_command, custom_data = "Level::BotAdmin", guild_only)]
+pub async fn guilds(context: Context<'_>) -> Result<()> {
+    list_guilds(&context).await
+}
+
+pub async fn list_guilds(discord: &impl Discord) -> Result<()> {
+    let guilds = discord.guilds();
+
+    if guilds.is_empty() {
+        discord.reply("not in any guild!").await?;
+        return Ok(())
+    }
+
+    let pages = guilds
+        .chunks(GUILDS_PER_PAGE)
+        .map(|guilds| {
+            guilds
+                .iter()
+                .map(|Guild { id, name, members }| format!("`{id}` {name} ({members} members)"))
+                .collect::<Vec<String>>()
+                .join("\n")
+        })
+        .collect::<Vec<String>>();
+    discord.paginate(&pages).await?;
+
+    Ok(())
+}
 
-    let shard_manager = framework.shard_manager();
+/// Make the bot leave a guild
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only)]
+pub async fn leave(context: Context<'_>, #[description = "GUILD_ID"] guild: String) -> Result<()> {
+    leave_guild(&context, &guild).await
+}
 
-    let Some(after) = after else {
-        response.push_str("\nshutting down...");
-        context.say(response).await?;
-        shard_manager.lock().await.shutdown_all().await;
+pub async fn leave_guild(discord: &impl Discord, guild: &str) -> Result<()> {
+    let Ok(guild_id) = guild.parse::<u64>().map(GuildId) else {
+        discord.reply("\"guild\" parameter must be GUILD_ID!").await?;
+        return Ok(())
+    };
 
+    let Some(name) = discord.guild_name(guild_id) else {
+        discord.reply(format!("not in guild {guild_id}!")).await?;
         return Ok(())
     };
 
-    if !(1..=60).contains(&after) {
-        response.push_str("\n\"after\" parameter must be in between 1 and 60!");
-        context.say(response).await?;
+    discord.reply(format!("leaving {name} ({guild_id})...")).await?;
+    discord.leave(guild_id).await?;
+
+    Ok(())
+}
+
+/// List bot admins
+#[poise::command(
+    prefix_command, slash_command, custom_data = "Level::Owner", guild_only,
+    subcommands("admin_list", "admin_add", "admin_remove"),
+)]
+pub async fn admin(context: Context<'_>) -> Result<()> {
+    let data = context.data();
+    list_admins(&context, &data.storage, &data.config()).await
+}
+
+/// List bot admins
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only, rename = "list")]
+pub async fn admin_list(context: Context<'_>) -> Result<()> {
+    let data = context.data();
+    list_admins(&context, &data.storage, &data.config()).await
+}
+
+/// Add a bot admin
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only, rename = "add")]
+pub async fn admin_add(context: Context<'_>, #[description = "User"] user: User) -> Result<()> {
+    add_admin(&context, &context.data().storage, &user).await
+}
+
+pub async fn add_admin(discord: &impl Discord, storage: &Storage, user: &User) -> Result<()> {
+    let mut admins = core::bot_admins(storage).await?;
+
+    if admins.contains(&user.id) {
+        discord.reply(format!("{} is already a bot admin!", user.tag())).await?;
+        return Ok(())
+    }
+
+    admins.push(user.id);
+    storage.set(Scope::Global, &BOT_ADMINS, &admins).await?;
+    discord.reply(format!("added {} as bot admin!", user.tag())).await?;
+
+    Ok(())
+}
 
+/// Remove a bot admin
+#[poise::command(prefix_command, slash_command, custom_data = "Level::Owner", guild_only, rename = "remove")]
+pub async fn admin_remove(context: Context<'_>, #[description = "User"] user: User) -> Result<()> {
+    let data = context.data();
+    remove_admin(&context, &data.storage, &data.config(), &user).await
+}
+
+/// Only removes admins added at runtime, the configured ones stay.
+pub async fn remove_admin(discord: &impl Discord, storage: &Storage, config: &base::Config, user: &User) -> Result<()> {
+    let mut admins = core::bot_admins(storage).await?;
+
+    if !admins.contains(&user.id) {
+        let response = match config.core.bot_admins.contains(&user.id) {
+            true => format!("{} is a bot admin through the config, remove them there!", user.tag()),
+            false => format!("{} is not a bot admin!", user.tag()),
+        };
+        discord.reply(response).await?;
         return Ok(())
     }
 
-    let channel_id = context.channel_id();
-    let http = context.serenity_context().http.clone();
+    admins.retain(|user_id| *user_id != user.id);
+    storage.set(Scope::Global, &BOT_ADMINS, &admins).await?;
+    discord.reply(format!("removed {} from bot admins!", user.tag())).await?;
 
-    let shutdown = tokio::spawn(async move {
-        let text = format!("\nshutting down in about {after} minutes!");
-        response.push_str(&text);
-        channel_id.say(&http, response).await?;
+    Ok(())
+}
 
-        let duration = Duration::from_secs(after * 60);
-        tokio::time::sleep(duration).await;
+pub async fn list_admins(discord: &impl Discord, storage: &Storage, config: &base::Config) -> Result<()> {
+    let configured = &config.core.bot_admins;
+    let added = core::bot_admins(storage).await?;
 
-        channel_id.say(&http, "shutting down...").await?;
-        shard_manager.lock().await.shutdown_all().await;
+    if configured.is_empty() && added.is_empty() {
+        discord.reply("no bot admins!").await?;
+        return Ok(())
+    }
 
-        Result::Ok(())
-    });
-    owner.shutdown.replace(shutdown);
+    let lines = configured
+        .iter()
+        .map(|user_id| format!("<@{user_id}> (config)"))
+        .chain(added.iter().map(|user_id| format!("<@{user_id}>")))
+        .collect::<Vec<String>>()
+        .join("\n");
+    discord.reply_quietly(format!("bot admins:\n{lines}")).await?;
 
     Ok(())
 }
+
+/// Id, connection stage and latency of `shard`.
+fn describe_shard(Shard { id, stage, latency, .. }: &Shard) -> String {
+    let latency = latency.map_or_else(|| "...".to_owned(), |latency| format!("{}ms", latency.as_millis()));
+
+    format!("#{id} {stage} {latency}")
+}
+
+/// The module state, only missing while the owner module is disabled.
+fn state(data: &base::Data) -> Result<ArcMut<owner::Data>> {
+    let module = data.modules.get::<owner::Module>().ok_or("owner module is disabled")?;
+    module.data().cloned().ok_or_else(|| "owner module is not initialized yet".into())
+}
+
+/// Resident set size read from procfs, unknown on other platforms.
+fn resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kilobytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+
+    Some(kilobytes * 1024)
+}
+
+/// `local` is the current guild and `global` is `None`.
+fn parse_scope(discord: &impl Discord, scope: &str) -> Option<Option<GuildId>> {
+    match scope {
+        "local" => Some(discord.guild_id()),
+        "global" => Some(None),
+        scope => scope.parse::<u64>().ok().map(|guild_id| Some(GuildId(guild_id))),
+    }
+}
+
+/// A duration from now, a UTC `HH:MM` (the next occurrence) or an RFC 3339 timestamp.
+fn parse_when(when: &str, now: OffsetDateTime) -> Option<OffsetDateTime> {
+    if let Some(duration) = helper::parse_duration(when) {
+        return now.checked_add(duration.try_into().ok()?)
+    }
+
+    if let Ok(at) = OffsetDateTime::parse(when, &Rfc3339) {
+        return Some(at)
+    }
+
+    let time = Time::parse(when, format_description!("[hour]:[minute]")).ok()?;
+    let at = now.replace_time(time);
+
+    match at > now {
+        true => Some(at),
+        false => Some(at + time::Duration::DAY),
+    }
+}
+
+fn parse_presence(entry: &str) -> Option<Presence> {
+    let mut words = entry.trim().splitn(2, char::is_whitespace);
+    let status = words.next()?.parse().ok()?;
+    let rest = words.next().unwrap_or_default().trim_start();
+
+    let mut words = rest.splitn(2, char::is_whitespace);
+    let activity = words.next().and_then(|word| word.parse().ok());
+    let name = match activity {
+        Some(_) => words.next().unwrap_or_default().trim(),
+        None => rest.trim(),
+    };
+    let name = (!name.is_empty()).then(|| name.to_owned());
+
+    Some(Presence { status, activity, name })
+}
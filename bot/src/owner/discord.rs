@@ -0,0 +1,236 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use poise::serenity_prelude::{self as serenity, Attachment, GuildId, SerenityError, ShardId};
+use serde_json::Value;
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+use crate::helper::{self, ArcMut, Context, Result};
+use crate::owner::{self, Presence, Rotation, Schedule};
+use crate::owner::{application, profile};
+
+/// How one of the shards run by this process is doing.
+#[derive(Clone)]
+pub struct Shard {
+    pub id: u64,
+    /// Connection stage as serenity names it, e.g. `connected` or `resuming`.
+    pub stage: String,
+    pub latency: Option<Duration>,
+    /// Guilds in the cache that this shard receives events for.
+    pub guilds: usize,
+}
+
+/// A guild the bot is in, as cached.
+#[derive(Clone)]
+pub struct Guild {
+    pub id: GuildId,
+    pub name: String,
+    pub members: u64,
+}
+
+/// How much the cache holds.
+#[derive(Clone, Copy, Default)]
+pub struct CacheSize {
+    pub guilds: usize,
+    pub channels: usize,
+    pub users: usize,
+}
+
+/// What owner commands ask of Discord, implemented by [`Context`] and by a
+/// recording fake so their logic runs in tests without a connection.
+#[poise::async_trait]
+pub trait Discord: Sync {
+    async fn reply(&self, content: impl Into<String> + Send + 'async_trait) -> Result<()>;
+
+    /// Replies without pinging the users and roles mentioned in `content`.
+    async fn reply_quietly(&self, content: String) -> Result<()>;
+
+    /// Shows `pages` one at a time, see [`helper::paginate`].
+    async fn paginate(&self, pages: &[String]) -> Result<()>;
+
+    /// Guild the command was invoked in.
+    fn guild_id(&self) -> Option<GuildId>;
+
+    async fn attachment(&self, attachment: &Attachment) -> Result<Vec<u8>>;
+
+    /// Fetches `url`, returning the reason shown to the user on failure.
+    async fn download(&self, url: &str) -> std::result::Result<Vec<u8>, String>;
+
+    async fn set_presence(&self, presence: &Presence);
+
+    /// Starts broadcasting the entries of `rotation` in turn, see [`Rotation::spawn`].
+    fn spawn_rotation(&self, rotation: Rotation, owner: &ArcMut<owner::Data>) -> JoinHandle<()>;
+
+    /// Starts counting down to `schedule`, see [`Schedule::spawn`].
+    fn spawn_schedule(&self, schedule: Schedule) -> JoinHandle<()>;
+
+    /// Application commands of this bot as they would be registered.
+    fn commands(&self, admin_guild: bool) -> Vec<Value>;
+
+    /// Application commands registered in `guild_id`, or globally for `None`.
+    async fn registered_commands(&self, guild_id: Option<GuildId>) -> Result<Vec<Value>>;
+
+    /// Replaces the application commands registered in `guild_id`, or globally for `None`.
+    async fn register_commands(&self, guild_id: Option<GuildId>, commands: Vec<Value>) -> Result<()>;
+
+    /// Edits a single field of the bot's profile, e.g. `avatar` or `username`.
+    async fn edit_profile(&self, field: &str, value: Value) -> std::result::Result<(), SerenityError>;
+
+    /// Name of `guild_id` if the bot is in it.
+    fn guild_name(&self, guild_id: GuildId) -> Option<String>;
+
+    /// Every guild the bot is in, most members first.
+    fn guilds(&self) -> Vec<Guild>;
+
+    fn cache_size(&self) -> CacheSize;
+
+    async fn leave(&self, guild_id: GuildId) -> Result<()>;
+
+    /// Shards run by this process, by id.
+    async fn shards(&self) -> Vec<Shard>;
+
+    /// Shards the bot is split into across every process.
+    fn shard_total(&self) -> u64;
+
+    /// Reconnects a shard run by this process.
+    async fn restart_shard(&self, id: u64);
+}
+
+#[poise::async_trait]
+impl Discord for Context<'_> {
+    async fn reply(&self, content: impl Into<String> + Send + 'async_trait) -> Result<()> {
+        self.say(content).await?;
+
+        Ok(())
+    }
+
+    async fn reply_quietly(&self, content: String) -> Result<()> {
+        self.send(|reply| reply.content(content).allowed_mentions(|mentions| mentions.empty_parse())).await?;
+
+        Ok(())
+    }
+
+    async fn paginate(&self, pages: &[String]) -> Result<()> {
+        helper::paginate(*self, pages).await
+    }
+
+    fn guild_id(&self) -> Option<GuildId> {
+        Context::guild_id(*self)
+    }
+
+    async fn attachment(&self, attachment: &Attachment) -> Result<Vec<u8>> {
+        Ok(attachment.download().await?)
+    }
+
+    async fn download(&self, url: &str) -> std::result::Result<Vec<u8>, String> {
+        profile::download(url, &self.data().shutdown.token()).await
+    }
+
+    async fn set_presence(&self, presence: &Presence) {
+        presence.broadcast(&self.framework().shard_manager()).await;
+    }
+
+    fn spawn_rotation(&self, rotation: Rotation, owner: &ArcMut<owner::Data>) -> JoinHandle<()> {
+        let data = self.data();
+        let shard_manager = self.framework().shard_manager();
+        rotation.spawn(self.serenity_context().clone(), shard_manager, owner.clone(), data.started, &data.shutdown)
+    }
+
+    fn spawn_schedule(&self, schedule: Schedule) -> JoinHandle<()> {
+        schedule.spawn(self.serenity_context().clone(), self.data())
+    }
+
+    fn commands(&self, admin_guild: bool) -> Vec<Value> {
+        application::commands(&self.framework().options().commands, admin_guild)
+    }
+
+    async fn registered_commands(&self, guild_id: Option<GuildId>) -> Result<Vec<Value>> {
+        application::current(&self.serenity_context().http, guild_id).await
+    }
+
+    async fn register_commands(&self, guild_id: Option<GuildId>, commands: Vec<Value>) -> Result<()> {
+        application::apply(&self.serenity_context().http, guild_id, commands).await
+    }
+
+    async fn edit_profile(&self, field: &str, value: Value) -> std::result::Result<(), SerenityError> {
+        profile::edit(&self.serenity_context().http, field, value).await
+    }
+
+    fn guild_name(&self, guild_id: GuildId) -> Option<String> {
+        self.serenity_context().cache.guild_field(guild_id, |guild| guild.name.clone())
+    }
+
+    fn guilds(&self) -> Vec<Guild> {
+        let cache = &self.serenity_context().cache;
+
+        let mut guilds = cache
+            .guilds()
+            .into_iter()
+            .filter_map(|id| {
+                cache.guild_field(id, |guild| Guild { id, name: guild.name.clone(), members: guild.member_count })
+            })
+            .collect::<Vec<Guild>>();
+        guilds.sort_by_key(|guild| std::cmp::Reverse(guild.members));
+
+        guilds
+    }
+
+    fn cache_size(&self) -> CacheSize {
+        let cache = &self.serenity_context().cache;
+
+        CacheSize {
+            guilds: cache.guild_count(),
+            channels: cache.guild_channel_count(),
+            users: cache.user_count(),
+        }
+    }
+
+    async fn leave(&self, guild_id: GuildId) -> Result<()> {
+        guild_id.leave(&self.serenity_context().http).await?;
+
+        Ok(())
+    }
+
+    async fn shards(&self) -> Vec<Shard> {
+        let total = self.shard_total().max(1);
+        let mut guilds = HashMap::new();
+        for guild_id in self.serenity_context().cache.guilds() {
+            *guilds.entry(serenity::shard_id(guild_id, total)).or_insert(0) += 1;
+        }
+
+        let runners = self.framework().shard_manager().lock().await.runners.clone();
+        let mut shards = runners
+            .lock().await
+            .iter()
+            .map(|(shard_id, runner)| Shard {
+                id: shard_id.0,
+                stage: runner.stage.to_string(),
+                latency: runner.latency,
+                guilds: guilds.get(&shard_id.0).copied().unwrap_or(0),
+            })
+            .collect::<Vec<Shard>>();
+        shards.sort_by_key(|shard| shard.id);
+
+        shards
+    }
+
+    fn shard_total(&self) -> u64 {
+        self.serenity_context().cache.shard_count()
+    }
+
+    async fn restart_shard(&self, id: u64) {
+        self.framework().shard_manager().lock().await.restart(ShardId(id)).await;
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use poise::{Event, FrameworkContext};
+use poise::serenity_prelude as serenity;
+use tokio::task::JoinHandle;
+use crate::base::Data;
+use crate::helper::{Error, Result};
+use crate::owner::application;
+use crate::owner::{Config, Module, Presence, COMMANDS, ROTATION, SCHEDULE};
+use crate::storage::Scope;
+
+pub async fn event_handler(
+    context: &serenity::Context,
+    event: &Event<'_>,
+    framework: FrameworkContext<'_, Data, Error>,
+    data: &Data,
+) -> Result<()> {
+    let Some(owner) = data.modules.get::<Module>().and_then(Module::data) else {
+        return Ok(())
+    };
+
+    if let Event::Ready { .. } = event {
+        // every shard sends its own ready, the commands only need syncing once
+        let synced = std::mem::replace(&mut owner.lock().await.synced, true);
+
+        if !synced {
+            if let Err(error) = sync(context, framework, data).await {
+                let message = "error syncing dev guild commands";
+                tracing::error!(message, error);
+                // retried on the next ready
+                owner.lock().await.synced = false;
+            }
+        }
+
+        if let Some(schedule) = data.storage.get(Scope::Global, &SCHEDULE).await? {
+            let mut owner = owner.lock().await;
+
+            if owner.shutdown.as_ref().map_or(true, JoinHandle::is_finished) {
+                // a deadline that passed while the bot was down is dropped
+                // rather than shutting down right after starting
+                if schedule.remaining().is_zero() {
+                    let message = "dropping overdue schedule";
+                    let action = schedule.action();
+                    tracing::warn!(message, action);
+                    data.storage.remove(Scope::Global, &SCHEDULE).await?;
+                } else {
+                    owner.shutdown.replace(schedule.spawn(context.clone(), data));
+                }
+            }
+        }
+    }
+
+    if let Event::Ready { .. } | Event::Resume { .. } = event {
+        if let Some(rotation) = data.storage.get(Scope::Global, &ROTATION).await? {
+            let mut state = owner.lock().await;
+
+            if state.rotation.as_ref().map_or(true, JoinHandle::is_finished) {
+                let shard_manager = framework.shard_manager();
+                let rotation = rotation.spawn(context.clone(), shard_manager, owner.clone(), data.started, &data.shutdown);
+                state.rotation.replace(rotation);
+            } else if let Some(presence) = &state.current {
+                // the running rotation only reached the shards connected back then
+                presence.apply(context).await;
+            }
+
+            return Ok(())
+        }
+
+        if let Some(presence) = Presence::stored(&data.storage, &data.config()).await? {
+            presence.apply(context).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Registers the commands in `owner.dev_guild` when they changed since the last sync.
+async fn sync(
+    context: &serenity::Context,
+    framework: FrameworkContext<'_, Data, Error>,
+    data: &Data,
+) -> Result<()> {
+    let config = Config::of(&data.config());
+
+    let Some(guild_id) = config.dev_guild else {
+        return Ok(())
+    };
+
+    let commands = &framework.options().commands;
+    let commands = application::commands(commands, Some(guild_id) == config.guild);
+    let fingerprint = application::fingerprint(&commands);
+    let scope = Scope::Guild(guild_id);
+
+    if data.storage.get(scope, &COMMANDS).await?.as_ref() != Some(&fingerprint) {
+        let message = "registering changed commands";
+        let guild = guild_id.0;
+        tracing::info!(message, guild);
+        application::apply(&context.http, Some(guild_id), commands).await?;
+        data.storage.set(scope, &COMMANDS, &fingerprint).await?;
+    }
+
+    Ok(())
+}
@@ -12,10 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod application;
 mod command;
+mod discord;
+mod hook;
+mod module;
 mod parameter;
+mod profile;
 mod state;
+#[cfg(test)]
+mod tests;
 
-pub use command::{avatar, group, presence, register, shutdown};
+pub use command::group;
+pub use discord::{CacheSize, Discord, Guild, Shard};
+pub use hook::event_handler;
+pub use module::Module;
 pub use parameter::{ActivityType, StatusType};
-pub use state::{data, Data};
+pub use state::{data, Config, Data, Presence, Rotation, Schedule, COMMANDS, PRESENCE, RENAMES, ROTATION, SCHEDULE};
@@ -0,0 +1,91 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::OnceLock;
+use poise::{Command, Event, Framework, FrameworkContext};
+use poise::serenity_prelude::{self as serenity, Ready};
+use toml::Value;
+use crate::base::{self, Data};
+use crate::helper::{ArcMut, Error, Result};
+use crate::owner;
+
+#[derive(Default)]
+pub struct Module {
+    data: OnceLock<ArcMut<owner::Data>>,
+}
+
+impl Module {
+    /// None until the first `Ready` built the state.
+    pub fn data(&self) -> Option<&ArcMut<owner::Data>> {
+        self.data.get()
+    }
+}
+
+#[poise::async_trait]
+impl base::Module for Module {
+    fn name(&self) -> &'static str {
+        "owner"
+    }
+
+    fn config(&self) -> Option<Value> {
+        Value::try_from(owner::Config::default()).ok()
+    }
+
+    fn validate(&self, config: &base::Config) -> Result<()> {
+        config.section::<owner::Config>(self.name())?;
+
+        Ok(())
+    }
+
+    fn commands(&self) -> Vec<Command<Data, Error>> {
+        vec![owner::group()]
+    }
+
+    async fn init(
+        &self,
+        context: &serenity::Context,
+        ready: &Ready,
+        framework: &Framework<Data, Error>,
+    ) -> Result<()> {
+        let data = owner::data(context, ready, framework).await?;
+        self.data.set(data).map_err(|_| "owner module is already initialized")?;
+
+        Ok(())
+    }
+
+    async fn event(
+        &self,
+        context: &serenity::Context,
+        event: &Event<'_>,
+        framework: FrameworkContext<'_, Data, Error>,
+        data: &Data,
+    ) -> Result<()> {
+        owner::event_handler(context, event, framework, data).await
+    }
+
+    /// The rotation and a pending schedule stop on their own once shutdown is
+    /// triggered, aborting them only makes sure nothing outlives the shards.
+    async fn shutdown(&self, _data: &Data) -> Result<()> {
+        let Some(owner) = self.data() else {
+            return Ok(())
+        };
+        let mut owner = owner.lock().await;
+
+        for task in [owner.rotation.take(), owner.shutdown.take()].into_iter().flatten() {
+            task.abort();
+        }
+
+        Ok(())
+    }
+}
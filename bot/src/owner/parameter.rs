@@ -13,8 +13,10 @@
 // limitations under the License.
 
 use poise::ChoiceParameter;
+use serde::{Deserialize, Serialize};
 
-#[derive(ChoiceParameter)]
+#[derive(Clone, Copy, ChoiceParameter, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ActivityType {
     Playing,
     Listening,
@@ -22,7 +24,8 @@ pub enum ActivityType {
     Competing,
 }
 
-#[derive(ChoiceParameter)]
+#[derive(Clone, Copy, ChoiceParameter, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum StatusType {
     Dnd,
     Idle,
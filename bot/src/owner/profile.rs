@@ -0,0 +1,278 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Cursor;
+use std::sync::OnceLock;
+use std::time::Duration;
+use image::ImageOutputFormat;
+use image::imageops::FilterType;
+use image::io::{Limits, Reader};
+use poise::serenity_prelude::{Http, HttpError, SerenityError};
+use serde_json::{Map, Value};
+use time::OffsetDateTime;
+use tokio_util::sync::CancellationToken;
+use crate::helper::Result;
+use crate::owner::RENAMES;
+use crate::storage::{Scope, Storage};
+
+/// Largest upload accepted for avatars and banners.
+pub const MAX_SIZE: usize = 8 * 1024 * 1024;
+
+/// Avatars are shown at most this large, larger crops are scaled down to it.
+pub const AVATAR_SIZE: u32 = 1024;
+
+/// Memory a decoded image may take, a small file can still decode to a huge one.
+const MAX_DECODED: u64 = 64 * 1024 * 1024;
+
+/// How long a download may take in total and to connect.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Discord allows two username changes per hour.
+const RENAME_LIMIT: usize = 2;
+const RENAME_PERIOD: i64 = 3600;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+}
+
+impl Format {
+    /// Identifies the format from its magic bytes, the file name and content
+    /// type are never trusted.
+    pub fn detect(bytes: &[u8]) -> Option<Format> {
+        let format = match bytes {
+            [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, ..] => Format::Png,
+            [0xff, 0xd8, 0xff, ..] => Format::Jpeg,
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Format::Gif,
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Format::Webp,
+            _ => return None,
+        };
+
+        Some(format)
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            Format::Png => "image/png",
+            Format::Jpeg => "image/jpeg",
+            Format::Gif => "image/gif",
+            Format::Webp => "image/webp",
+        }
+    }
+}
+
+pub struct Image {
+    pub format: Format,
+    pub bytes: Vec<u8>,
+}
+
+impl Image {
+    /// Checks size and format, returning the reason shown to the user on failure.
+    pub fn new(bytes: Vec<u8>) -> std::result::Result<Image, String> {
+        if bytes.len() > MAX_SIZE {
+            let size = bytes.len() / 1024;
+            let limit = MAX_SIZE / 1024;
+            return Err(format!("image is {size} KiB, the limit is {limit} KiB!"))
+        }
+
+        let Some(format) = Format::detect(&bytes) else {
+            return Err("image must be a png, jpeg, gif or webp file!".to_owned())
+        };
+
+        Ok(Image { format, bytes })
+    }
+
+    /// The largest centered square of the image scaled down to [`AVATAR_SIZE`],
+    /// as a png. Animated images keep their first frame.
+    pub fn crop_square(&self) -> Option<Image> {
+        let mut limits = Limits::default();
+        limits.max_alloc = Some(MAX_DECODED);
+
+        let mut reader = Reader::new(Cursor::new(&self.bytes)).with_guessed_format().ok()?;
+        reader.limits(limits);
+        let decoded = reader.decode().ok()?;
+
+        let (width, height) = (decoded.width(), decoded.height());
+        let size = width.min(height);
+        let mut square = decoded.crop_imm((width - size) / 2, (height - size) / 2, size, size);
+        if size > AVATAR_SIZE {
+            square = square.resize_exact(AVATAR_SIZE, AVATAR_SIZE, FilterType::Lanczos3);
+        }
+
+        let mut bytes = Vec::new();
+        square.write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png).ok()?;
+        Some(Image { format: Format::Png, bytes })
+    }
+
+    pub fn data_uri(&self) -> String {
+        format!("data:{};base64,{}", self.format.mime(), base64::encode(&self.bytes))
+    }
+
+    /// Width and height read from the file header.
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        let bytes = self.bytes.as_slice();
+        let u16le = |at: usize| Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32);
+        let u16be = |at: usize| Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32);
+        let u32be = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+        let u24le = |at: usize| {
+            let bytes = bytes.get(at..at + 3)?;
+            Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+        };
+
+        match self.format {
+            Format::Png => Some((u32be(16)?, u32be(20)?)),
+            Format::Gif => Some((u16le(6)?, u16le(8)?)),
+            Format::Webp => match bytes.get(12..16)? {
+                b"VP8 " => Some((u16le(26)? & 0x3fff, u16le(28)? & 0x3fff)),
+                b"VP8L" => {
+                    let bits = u32::from_le_bytes(bytes.get(21..25)?.try_into().ok()?);
+                    Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+                }
+                b"VP8X" => Some((u24le(24)? + 1, u24le(27)? + 1)),
+                _ => None,
+            },
+            Format::Jpeg => {
+                // walks the segments up to the first start-of-frame marker
+                let mut at = 2;
+                loop {
+                    if *bytes.get(at)? != 0xff {
+                        return None
+                    }
+
+                    let marker = *bytes.get(at + 1)?;
+                    let length = u16be(at + 2)? as usize;
+
+                    if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+                        return Some((u16be(at + 7)?, u16be(at + 5)?))
+                    }
+
+                    at += 2 + length;
+                }
+            }
+        }
+    }
+}
+
+/// Fetches an image from `url`, returning the reason shown to the user on
+/// failure or once `token` is cancelled.
+pub async fn download(url: &str, token: &CancellationToken) -> std::result::Result<Vec<u8>, String> {
+    tokio::select!{
+        bytes = fetch(url) => bytes,
+        _ = token.cancelled() => Err("shutting down, try again later!".to_owned()),
+    }
+}
+
+/// The client shared by every download, so connections are pooled and a
+/// stalled server can't hold the command forever.
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(DOWNLOAD_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .expect("failed to build the download client")
+    })
+}
+
+async fn fetch(url: &str) -> std::result::Result<Vec<u8>, String> {
+    let limit = MAX_SIZE / 1024;
+    let mut response = match client().get(url).send().await.and_then(|response| response.error_for_status()) {
+        Ok(response) => response,
+        Err(error) => return Err(format!("couldn't download the image: {error}!")),
+    };
+
+    if response.content_length().is_some_and(|length| length as usize > MAX_SIZE) {
+        return Err(format!("image is larger than {limit} KiB!"))
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok());
+    if content_type.is_some_and(|content_type| !content_type.starts_with("image/")) {
+        return Err(format!("url doesn't point to an image but {}!", content_type.unwrap_or_default()))
+    }
+
+    // the length header is optional, so the body is cut off once it grows too large
+    let mut bytes = Vec::new();
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) if bytes.len() + chunk.len() > MAX_SIZE => {
+                return Err(format!("image is larger than {limit} KiB!"))
+            }
+            Ok(Some(chunk)) => bytes.extend_from_slice(&chunk),
+            Ok(None) => return Ok(bytes),
+            Err(error) => return Err(format!("couldn't download the image: {error}!")),
+        }
+    }
+}
+
+/// Edits a single field of the bot's profile, e.g. `avatar`, `banner` or `username`.
+pub async fn edit(http: &Http, field: &str, value: Value) -> std::result::Result<(), SerenityError> {
+    let mut map = Map::new();
+    map.insert(field.to_owned(), value);
+    http.edit_profile(&map).await?;
+
+    Ok(())
+}
+
+/// The message Discord sent along with a rejected request, if that's what failed.
+pub fn rejection(error: &SerenityError) -> Option<String> {
+    match error {
+        SerenityError::Http(error) => match error.as_ref() {
+            HttpError::UnsuccessfulRequest(response) => {
+                let mut message = response.error.message.to_lowercase();
+                for error in &response.error.errors {
+                    message.push_str(&format!(", {}", error.message.to_lowercase()));
+                }
+
+                Some(message)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Seconds until another rename is allowed, none if it can happen right away.
+pub async fn rename_cooldown(storage: &Storage) -> Result<Option<u64>> {
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let renames = storage.get(Scope::Global, &RENAMES).await?.unwrap_or_default();
+    let recent = renames
+        .iter()
+        .filter(|renamed| now - **renamed < RENAME_PERIOD)
+        .collect::<Vec<&i64>>();
+
+    if recent.len() < RENAME_LIMIT {
+        return Ok(None)
+    }
+
+    let oldest = recent.iter().copied().min().copied().unwrap_or(now);
+    Ok(Some((oldest + RENAME_PERIOD - now).max(1) as u64))
+}
+
+pub async fn record_rename(storage: &Storage) -> Result<()> {
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let mut renames = storage.get(Scope::Global, &RENAMES).await?.unwrap_or_default();
+    renames.retain(|renamed| now - renamed < RENAME_PERIOD);
+    renames.push(now);
+    storage.set(Scope::Global, &RENAMES, &renames).await?;
+
+    Ok(())
+}
@@ -12,11 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::Arc;
+use std::time::Instant;
 use poise::Framework;
-use poise::serenity_prelude::{Context, Ready};
+use poise::serenity_prelude::{Activity, ChannelId, Context, GuildId, OnlineStatus, Ready, ShardManager};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
+use tokio::time::Duration;
 use crate::{base, helper};
 use crate::helper::{ArcMut, Error, Result};
+use crate::owner::{ActivityType, StatusType};
+use crate::storage::{Key, Scope, Storage};
+
+pub const COMMANDS: Key<String> = Key::new("owner.commands");
+pub const PRESENCE: Key<Presence> = Key::new("owner.presence");
+pub const RENAMES: Key<Vec<i64>> = Key::new("owner.renames");
+pub const ROTATION: Key<Rotation> = Key::new("owner.rotation");
+pub const SCHEDULE: Key<Schedule> = Key::new("owner.schedule");
 
 pub async fn data(
     _: &Context,
@@ -24,12 +38,249 @@ pub async fn data(
     _: &Framework<base::Data, Error>,
 ) -> Result<ArcMut<Data>> {
     let data = helper::arcmut(Data {
+        rotation: None,
+        current: None,
         shutdown: None,
+        synced: false,
     });
 
     Ok(data)
 }
 
 pub struct Data {
-    pub shutdown: Option<JoinHandle<Result<()>>>,
+    pub rotation: Option<JoinHandle<()>>,
+    /// Entry the rotation broadcast last, for shards connecting in between.
+    pub current: Option<Presence>,
+    pub shutdown: Option<JoinHandle<()>>,
+    /// Whether the dev guild commands were synced since starting.
+    pub synced: bool,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub guild: Option<GuildId>,
+    pub dev_guild: Option<GuildId>,
+    pub presence: Option<Presence>,
+    pub announce: Announce,
+}
+
+impl Config {
+    /// The `[owner]` section of `config`, which was checked when it loaded.
+    pub fn of(config: &base::Config) -> Config {
+        config.section("owner").unwrap_or_default()
+    }
+}
+
+/// Countdown messages for scheduled shutdowns and restarts.
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct Announce {
+    pub channel: Option<ChannelId>,
+    /// Seconds before the deadline at which to announce it.
+    pub before: Vec<u64>,
+}
+
+impl Default for Announce {
+    fn default() -> Self {
+        Announce {
+            channel: None,
+            before: vec![3600, 600, 60],
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Presence {
+    pub status: StatusType,
+    pub activity: Option<ActivityType>,
+    pub name: Option<String>,
+}
+
+impl Presence {
+    /// The last presence set by `owner presence`, falling back to the config.
+    pub async fn stored(storage: &Storage, config: &base::Config) -> Result<Option<Presence>> {
+        let presence = match storage.get(Scope::Global, &PRESENCE).await? {
+            Some(presence) => Some(presence),
+            None => Config::of(config).presence,
+        };
+
+        Ok(presence)
+    }
+
+    pub fn activity(&self) -> Option<Activity> {
+        let name = self.name.clone().unwrap_or_else(|| "...".to_owned());
+
+        self.activity.map(|activity| {
+            match activity {
+                ActivityType::Playing => Activity::playing(name),
+                ActivityType::Listening => Activity::listening(name),
+                ActivityType::Watching => Activity::watching(name),
+                ActivityType::Competing => Activity::competing(name),
+            }
+        })
+    }
+
+    pub fn status(&self) -> OnlineStatus {
+        match self.status {
+            StatusType::Dnd => OnlineStatus::DoNotDisturb,
+            StatusType::Idle => OnlineStatus::Idle,
+            StatusType::Invisible => OnlineStatus::Invisible,
+            StatusType::Offline => OnlineStatus::Offline,
+            StatusType::Online => OnlineStatus::Online,
+        }
+    }
+
+    /// Sets the presence on the shard of `context` only, e.g. one that just connected.
+    pub async fn apply(&self, context: &Context) {
+        context.set_presence(self.activity(), self.status()).await;
+    }
+
+    /// Sets the presence on every shard run by this process.
+    pub async fn broadcast(&self, shard_manager: &Mutex<ShardManager>) {
+        let runners = shard_manager.lock().await.runners.clone();
+
+        for runner in runners.lock().await.values() {
+            runner.runner_tx.set_presence(self.activity(), self.status());
+        }
+    }
+
+    /// Fills `{guilds}`, `{users}` and `{uptime}` in the activity name. Without
+    /// member intents `{users}` only counts the users seen since connecting.
+    pub fn render(&self, context: &Context, started: Instant) -> Presence {
+        let name = self.name.as_ref().map(|name| {
+            name.replace("{guilds}", &context.cache.guild_count().to_string())
+                .replace("{users}", &context.cache.user_count().to_string())
+                .replace("{uptime}", &helper::format_duration(started.elapsed()))
+        });
+
+        Presence { name, ..self.clone() }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut description = self.status.to_string().to_lowercase();
+
+        if let Some(activity) = self.activity {
+            description.push(' ');
+            description.push_str(&activity.to_string().to_lowercase());
+        }
+
+        if let Some(name) = &self.name {
+            description.push(' ');
+            description.push_str(name);
+        }
+
+        description
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Rotation {
+    pub interval: u64,
+    pub entries: Vec<Presence>,
+}
+
+impl Rotation {
+    /// Broadcasts the entries in turn, keeping the current one in `owner`.
+    pub fn spawn(
+        self,
+        context: Context,
+        shard_manager: Arc<Mutex<ShardManager>>,
+        owner: ArcMut<Data>,
+        started: Instant,
+        shutdown: &base::Shutdown,
+    ) -> JoinHandle<()> {
+        shutdown.spawn(async move {
+            let interval = Duration::from_secs(self.interval);
+
+            for presence in self.entries.iter().cycle() {
+                let presence = presence.render(&context, started);
+                presence.broadcast(&shard_manager).await;
+                owner.lock().await.current.replace(presence);
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+}
+
+/// A pending `owner shutdown` or `owner restart`, kept in storage until it fires.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct Schedule {
+    /// Unix timestamp in seconds.
+    pub at: i64,
+    pub restart: bool,
+}
+
+impl Schedule {
+    pub fn remaining(&self) -> Duration {
+        let remaining = self.at - OffsetDateTime::now_utc().unix_timestamp();
+        Duration::from_secs(remaining.max(0) as u64)
+    }
+
+    pub fn action(&self) -> &'static str {
+        match self.restart {
+            true => "restart",
+            false => "shutdown",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let remaining = helper::format_duration(self.remaining());
+        format!("{} in about {remaining}, at <t:{}:f>", self.action(), self.at)
+    }
+
+    /// Announces the countdown to the configured channel, then triggers the
+    /// shutdown or restart.
+    pub fn spawn(self, context: Context, data: &base::Data) -> JoinHandle<()> {
+        let reloader = data.reloader.clone();
+        let storage = data.storage.clone();
+        let shutdown = data.shutdown.clone();
+
+        data.shutdown.spawn(async move {
+            let mut before = Config::of(&reloader.config()).announce.before;
+            before.sort_unstable_by(|a, b| b.cmp(a));
+
+            for before in before {
+                let remaining = self.remaining();
+                let before = Duration::from_secs(before);
+                if remaining <= before {
+                    continue
+                }
+
+                tokio::time::sleep(remaining - before).await;
+                let text = format!("{} in about {}!", self.action(), helper::format_duration(before));
+                self.announce(&context, &reloader, text).await;
+            }
+
+            tokio::time::sleep(self.remaining()).await;
+
+            if let Err(error) = storage.remove(Scope::Global, &SCHEDULE).await {
+                let message = "error removing schedule";
+                tracing::error!(message, error);
+            }
+
+            let text = match self.restart {
+                true => "restarting...",
+                false => "shutting down...",
+            };
+            self.announce(&context, &reloader, text.to_owned()).await;
+
+            match self.restart {
+                true => shutdown.restart(),
+                false => shutdown.trigger(),
+            };
+        })
+    }
+
+    async fn announce(&self, context: &Context, reloader: &base::Reloader, text: String) {
+        let Some(channel_id) = Config::of(&reloader.config()).announce.channel else {
+            return
+        };
+
+        if let Err(error) = channel_id.say(&context.http, text).await {
+            let message = "error announcing schedule";
+            let error = error.to_string();
+            tracing::warn!(message, error);
+        }
+    }
 }
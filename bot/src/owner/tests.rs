@@ -0,0 +1,700 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Cursor;
+use hyper::{Method, StatusCode};
+use image::{DynamicImage, ImageFormat, ImageOutputFormat, Rgb, RgbImage};
+use poise::serenity_prelude::{Attachment, GuildId, User, UserId};
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+use crate::{base, helper};
+use crate::core::BOT_ADMINS;
+use crate::mock::{Harness, Recorder, APPLICATION, CHANNEL, GUILD, MEMBER, OWNER};
+use crate::owner::{application, command, profile, ActivityType, CacheSize, Guild, Presence, Shard, StatusType, COMMANDS, PRESENCE, RENAMES, ROTATION, SCHEDULE};
+use crate::owner::profile::{Format, Image};
+use crate::storage::{Memory, Scope, Storage};
+
+#[test]
+fn diff_matches_commands_discord_filled_in() {
+    let desired = [json!({ "name": "help", "description": "Show help", "options": [] })];
+    let current = [json!({
+        "id": "1",
+        "application_id": "100",
+        "version": "1",
+        "type": 1,
+        "name": "help",
+        "description": "Show help",
+        "default_member_permissions": null,
+        "dm_permission": true,
+    })];
+
+    assert!(application::Diff::new(&current, &desired).is_empty());
+}
+
+#[test]
+fn diff_lists_added_changed_and_removed_commands() {
+    let desired = [
+        json!({ "name": "help", "description": "Show help" }),
+        json!({ "name": "owner", "description": "Owner commands" }),
+    ];
+    let current = [
+        json!({ "type": 1, "name": "help", "description": "Show the help" }),
+        json!({ "type": 1, "name": "ping", "description": "Pong" }),
+    ];
+
+    let diff = application::Diff::new(&current, &desired);
+    assert_eq!(diff.added, ["owner"]);
+    assert_eq!(diff.changed, ["help"]);
+    assert_eq!(diff.removed, ["ping"]);
+    assert_eq!(diff.describe(), "+ owner\n~ help\n- ping");
+}
+
+#[tokio::test]
+async fn presence_is_sent_to_the_gateway_and_stored() {
+    let harness = Harness::start().await;
+
+    assert_eq!(harness.command("!owner presence idle playing chess").await, "setting presence...");
+
+    let presence = harness.gateway.wait(3).await;
+    assert_eq!(presence["status"], "idle");
+    assert_eq!(presence["game"]["name"], "chess");
+
+    let stored = harness.data().await.storage.get(Scope::Global, &PRESENCE).await.unwrap();
+    assert!(stored.is_some());
+}
+
+#[tokio::test]
+async fn running_rotation_reaches_shards_connecting_later() {
+    let harness = Harness::start().await;
+
+    let reply = harness.command("!owner presence rotate 60 dnd watching the logs | idle").await;
+    assert_eq!(reply, "rotating 2 presences every 60 seconds...");
+    assert_eq!(harness.gateway.wait(3).await["status"], "dnd");
+
+    harness.ready().await;
+    let presence = harness.gateway.wait(3).await;
+    assert_eq!(presence["status"], "dnd");
+    assert_eq!(presence["game"]["name"], "the logs");
+}
+
+#[tokio::test]
+async fn presence_rejects_unknown_status() {
+    let harness = Harness::start().await;
+
+    harness.send(OWNER, "!owner presence sleepy").await;
+    let reply = harness.reply().await;
+    assert!(reply.contains("sleepy"), "{reply}");
+}
+
+#[tokio::test]
+async fn register_puts_member_commands_into_the_guild() {
+    let harness = Harness::start().await;
+    let path = format!("/applications/{APPLICATION}/guilds/{GUILD}/commands");
+
+    let reply = harness.command("!owner register local").await;
+    assert_eq!(reply, format!("registering commands for {GUILD}...\n```diff\n+ help\n```"));
+
+    harness.rest.wait(Method::GET, &path).await;
+    let call = harness.rest.wait(Method::PUT, &path).await;
+    let names = call.body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|command| command["name"].as_str().unwrap())
+        .collect::<Vec<&str>>();
+    // owner commands stay out of guilds other than `owner.guild`
+    assert_eq!(names, ["help"]);
+}
+
+#[tokio::test]
+async fn register_skips_commands_already_registered() {
+    let harness = Harness::start().await;
+    let path = format!("/applications/{APPLICATION}/guilds/{GUILD}/commands");
+
+    let registered = application::commands(&harness.framework.options().commands, false)
+        .into_iter()
+        .enumerate()
+        .map(|(id, mut command)| {
+            command["id"] = json!(id.to_string());
+            command["application_id"] = json!(APPLICATION.to_string());
+            command["version"] = json!("1");
+            command["type"] = json!(1);
+            command
+        })
+        .collect();
+    harness.rest.respond(Method::GET, &path, StatusCode::OK, Value::Array(registered));
+
+    let reply = harness.command("!owner register local").await;
+    assert_eq!(reply, "registered commands are already up to date!");
+    assert!(harness.rest.calls().iter().all(|call| call.method != Method::PUT));
+}
+
+#[tokio::test]
+async fn shutdown_triggers_the_shutdown() {
+    let harness = Harness::start().await;
+
+    assert_eq!(harness.command("!owner shutdown").await, "shutting down...");
+    assert!(harness.data().await.shutdown.is_triggered());
+}
+
+#[tokio::test]
+async fn scheduled_shutdown_is_stored_until_cancelled() {
+    let harness = Harness::start().await;
+    let storage = &harness.data().await.storage;
+
+    let reply = harness.command("!owner shutdown 2h").await;
+    assert!(reply.starts_with("scheduled shutdown"), "{reply}");
+    assert!(storage.get(Scope::Global, &SCHEDULE).await.unwrap().is_some());
+    assert!(!harness.data().await.shutdown.is_triggered());
+
+    assert_eq!(harness.command("!owner shutdown cancel").await, "cancelled scheduled shutdown!");
+    assert!(storage.get(Scope::Global, &SCHEDULE).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn members_are_refused() {
+    let harness = Harness::start().await;
+
+    harness.send(MEMBER, "!owner shutdown").await;
+    assert_eq!(harness.reply().await, "only bot owners can run this command!");
+    assert!(!harness.data().await.shutdown.is_triggered());
+    assert!(harness.rest.calls().iter().all(|call| call.path != format!("/channels/{CHANNEL}/messages")));
+}
+
+#[tokio::test]
+async fn refusals_are_counted_apart_from_failures() {
+    let harness = Harness::start().await;
+
+    harness.send(MEMBER, "!owner shutdown").await;
+    harness.reply().await;
+
+    let metrics = &harness.data().await.metrics;
+    assert_eq!(metrics.denials.with_label_values(&["owner shutdown", "check"]).get(), 1);
+    assert_eq!(metrics.failures.with_label_values(&["owner shutdown", "check"]).get(), 0);
+    assert_eq!(metrics.commands.with_label_values(&["owner shutdown"]).get(), 0);
+}
+
+#[tokio::test]
+async fn dev_guild_commands_sync_once() {
+    let harness = Harness::with_config(|config| {
+        let owner = super::Config {
+            dev_guild: Some(GuildId(GUILD)),
+            presence: Some(Presence { status: StatusType::Idle, activity: None, name: None }),
+            ..super::Config::default()
+        };
+        config.sections.insert("owner".to_owned(), toml::Value::try_from(owner).unwrap());
+    }).await;
+    let path = format!("/applications/{APPLICATION}/guilds/{GUILD}/commands");
+
+    harness.rest.wait(Method::PUT, &path).await;
+    assert_eq!(harness.gateway.wait(3).await["status"], "idle");
+
+    // forgetting the fingerprint would make any further sync register again
+    harness.data().await.storage.remove(Scope::Guild(GuildId(GUILD)), &COMMANDS).await.unwrap();
+    harness.ready().await;
+
+    // the presence is restored after the sync would have run
+    assert_eq!(harness.gateway.wait(3).await["status"], "idle");
+    let syncs = harness.rest.calls().iter().filter(|call| call.method == Method::PUT && call.path == path).count();
+    assert_eq!(syncs, 0);
+}
+
+/// A png header claiming `width` by `height`, enough for format and size checks.
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0, 0, 0, 13];
+    bytes.extend_from_slice(b"IHDR");
+    bytes.extend_from_slice(&width.to_be_bytes());
+    bytes.extend_from_slice(&height.to_be_bytes());
+    bytes
+}
+
+/// A complete image of `width` by `height` whose pixels encode their position,
+/// wrapping around past 25.
+fn encoded(width: u32, height: u32, format: ImageOutputFormat) -> Vec<u8> {
+    let pixels = RgbImage::from_fn(width, height, |x, y| Rgb([(x * 10) as u8, (y * 10) as u8, 0]));
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgb8(pixels).write_to(&mut Cursor::new(&mut bytes), format).unwrap();
+    bytes
+}
+
+fn decoded(bytes: &[u8]) -> RgbImage {
+    image::load_from_memory_with_format(bytes, ImageFormat::Png).unwrap().to_rgb8()
+}
+
+#[test]
+fn crop_square_keeps_the_center() {
+    let image = Image::new(encoded(6, 2, ImageOutputFormat::Png)).unwrap();
+
+    let cropped = image.crop_square().unwrap();
+
+    assert!(cropped.format == Format::Png);
+    let pixels = decoded(&cropped.bytes);
+    assert_eq!(pixels.dimensions(), (2, 2));
+    assert_eq!(pixels.get_pixel(0, 0), &Rgb([20, 0, 0]));
+    assert_eq!(pixels.get_pixel(1, 1), &Rgb([30, 10, 0]));
+}
+
+#[test]
+fn crop_square_reads_every_format_and_scales_down() {
+    let image = Image::new(encoded(1200, 1100, ImageOutputFormat::Jpeg(90))).unwrap();
+
+    let cropped = image.crop_square().unwrap();
+
+    assert_eq!(cropped.dimensions(), Some((profile::AVATAR_SIZE, profile::AVATAR_SIZE)));
+}
+
+#[test]
+fn crop_square_skips_what_it_cannot_decode() {
+    let image = Image::new(png(300, 200)).unwrap();
+    assert!(image.crop_square().is_none());
+}
+
+fn attachment(url: &str, size: u64) -> Attachment {
+    serde_json::from_value(json!({
+        "id": "1",
+        "filename": "image.png",
+        "size": size,
+        "url": url,
+        "proxy_url": url,
+    })).unwrap()
+}
+
+#[tokio::test]
+async fn set_image_requires_an_attachment_or_url() {
+    let discord = Recorder::default();
+
+    command::set_image(&discord, "avatar", None, None, false).await.unwrap();
+
+    assert_eq!(discord.replies(), ["attachment or url is required!"]);
+    assert!(discord.edits().is_empty());
+}
+
+#[tokio::test]
+async fn set_image_rejects_oversized_attachments() {
+    let discord = Recorder::default();
+    let attachment = attachment("https://cdn/large.png", profile::MAX_SIZE as u64 + 1);
+
+    command::set_image(&discord, "avatar", Some(attachment), None, false).await.unwrap();
+
+    assert_eq!(discord.replies(), ["image is 8192 KiB, the limit is 8192 KiB!"]);
+    assert!(discord.edits().is_empty());
+}
+
+#[tokio::test]
+async fn set_image_rejects_unknown_formats() {
+    let mut discord = Recorder::default();
+    discord.files.insert("https://cdn/notes.txt".to_owned(), b"plain text".to_vec());
+
+    command::set_image(&discord, "banner", None, Some("https://cdn/notes.txt".to_owned()), false).await.unwrap();
+
+    assert_eq!(discord.replies(), ["image must be a png, jpeg, gif or webp file!"]);
+    assert!(discord.edits().is_empty());
+}
+
+#[tokio::test]
+async fn set_image_reports_failed_downloads() {
+    let discord = Recorder::default();
+
+    command::set_image(&discord, "banner", None, Some("https://cdn/missing.png".to_owned()), false).await.unwrap();
+
+    assert_eq!(discord.replies(), ["couldn't download the image: 404 not found!"]);
+    assert!(discord.edits().is_empty());
+}
+
+#[tokio::test]
+async fn download_gives_up_on_shutdown() {
+    // accepts the connection but never answers
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/avatar.png", listener.local_addr().unwrap());
+    let token = CancellationToken::new();
+
+    tokio::spawn({
+        let token = token.clone();
+        async move {
+            let _connection = listener.accept().await;
+            token.cancel();
+            std::future::pending::<()>().await
+        }
+    });
+
+    let download = tokio::time::timeout(Duration::from_secs(5), profile::download(&url, &token)).await;
+    assert_eq!(download.unwrap(), Err("shutting down, try again later!".to_owned()));
+}
+
+#[tokio::test]
+async fn download_stops_past_the_size_limit() {
+    // streams without a content length, closing the connection would end the body
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/avatar.png", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        let (mut connection, _) = listener.accept().await.unwrap();
+        let mut request = [0; 1024];
+        let _ = connection.read(&mut request).await;
+        let _ = connection.write_all(b"HTTP/1.1 200 OK\r\ncontent-type: image/png\r\n\r\n").await;
+        let chunk = [0; 64 * 1024];
+        while connection.write_all(&chunk).await.is_ok() {}
+    });
+
+    let download = profile::download(&url, &CancellationToken::new()).await;
+    assert_eq!(download, Err("image is larger than 8192 KiB!".to_owned()));
+}
+
+#[tokio::test]
+async fn set_image_uploads_and_warns_about_cropping() {
+    let mut discord = Recorder::default();
+    let bytes = png(300, 200);
+    discord.files.insert("https://cdn/wide.png".to_owned(), bytes.clone());
+    let attachment = attachment("https://cdn/wide.png", bytes.len() as u64);
+
+    command::set_image(&discord, "avatar", Some(attachment), None, false).await.unwrap();
+
+    assert_eq!(discord.replies(), ["setting avatar...\nimage is 300x200, discord will crop it to a square!"]);
+    let uri = format!("data:image/png;base64,{}", base64::encode(&bytes));
+    assert_eq!(discord.edits(), [("avatar".to_owned(), Value::String(uri))]);
+}
+
+#[tokio::test]
+async fn set_image_crops_to_a_square_on_request() {
+    let mut discord = Recorder::default();
+    discord.files.insert("https://cdn/wide.png".to_owned(), encoded(3, 1, ImageOutputFormat::Png));
+
+    command::set_image(&discord, "avatar", None, Some("https://cdn/wide.png".to_owned()), true).await.unwrap();
+
+    assert_eq!(discord.replies(), ["setting avatar...\ncropped the 3x1 image to 1x1!"]);
+    let edits = discord.edits();
+    let uri = edits[0].1.as_str().unwrap();
+    let cropped = decoded(&base64::decode(uri.strip_prefix("data:image/png;base64,").unwrap()).unwrap());
+    assert_eq!(cropped.into_raw(), vec![10, 0, 0]);
+}
+
+#[tokio::test]
+async fn set_image_uploads_what_it_cannot_crop_as_is() {
+    let mut discord = Recorder::default();
+    let bytes = png(300, 200);
+    discord.files.insert("https://cdn/wide.png".to_owned(), bytes.clone());
+
+    command::set_image(&discord, "avatar", None, Some("https://cdn/wide.png".to_owned()), true).await.unwrap();
+
+    assert_eq!(discord.replies(), ["setting avatar...\ncouldn't crop the 300x200 image, uploading it as is!"]);
+    let uri = format!("data:image/png;base64,{}", base64::encode(&bytes));
+    assert_eq!(discord.edits(), [("avatar".to_owned(), Value::String(uri))]);
+}
+
+#[tokio::test]
+async fn reset_image_clears_the_field() {
+    let discord = Recorder::default();
+
+    command::reset_image(&discord, "banner").await.unwrap();
+
+    assert_eq!(discord.replies(), ["resetting banner..."]);
+    assert_eq!(discord.edits(), [("banner".to_owned(), Value::Null)]);
+}
+
+#[tokio::test]
+async fn rename_validates_length() {
+    let discord = Recorder::default();
+    let storage = Storage::new(Memory::default());
+
+    command::rename(&discord, &storage, " k ").await.unwrap();
+
+    assert_eq!(discord.replies(), ["\"name\" parameter must be in between 2 and 32 characters!"]);
+    assert!(discord.edits().is_empty());
+}
+
+#[tokio::test]
+async fn rename_is_limited_to_two_per_hour() {
+    let discord = Recorder::default();
+    let storage = Storage::new(Memory::default());
+
+    command::rename(&discord, &storage, "kanae").await.unwrap();
+    command::rename(&discord, &storage, "kanae two").await.unwrap();
+    command::rename(&discord, &storage, "kanae three").await.unwrap();
+
+    let replies = discord.replies();
+    assert_eq!(replies[..2], ["renaming to kanae...", "renaming to kanae two..."]);
+    assert!(replies[2].starts_with("discord only allows 2 username changes per hour"), "{}", replies[2]);
+    assert_eq!(discord.edits().len(), 2);
+    assert_eq!(storage.get(Scope::Global, &RENAMES).await.unwrap().map(|renames| renames.len()), Some(2));
+}
+
+#[tokio::test]
+async fn rejected_renames_are_not_counted() {
+    let discord = Recorder { rejection: Some("Invalid Form Body".to_owned()), ..Recorder::default() };
+    let storage = Storage::new(Memory::default());
+
+    command::rename(&discord, &storage, "kanae").await.unwrap();
+
+    assert_eq!(discord.replies(), ["renaming to kanae...", "discord rejected the username: invalid form body!"]);
+    assert!(discord.edits().is_empty());
+    assert_eq!(storage.get(Scope::Global, &RENAMES).await.unwrap(), None);
+}
+
+fn owner() -> helper::ArcMut<super::Data> {
+    helper::arcmut(super::Data { rotation: None, current: None, shutdown: None, synced: false })
+}
+
+#[tokio::test]
+async fn set_presence_replaces_the_rotation() {
+    let discord = Recorder::default();
+    let storage = Storage::new(Memory::default());
+    let owner = owner();
+    let presence = Presence {
+        status: StatusType::Dnd,
+        activity: Some(ActivityType::Watching),
+        name: Some("the logs".to_owned()),
+    };
+
+    command::set_presence(&discord, &storage, &owner, presence).await.unwrap();
+
+    assert_eq!(discord.replies(), ["setting presence..."]);
+    let presences = discord.presences.lock().unwrap().iter().map(Presence::describe).collect::<Vec<String>>();
+    assert_eq!(presences, ["dnd watching the logs"]);
+    assert!(storage.get(Scope::Global, &PRESENCE).await.unwrap().is_some());
+    assert!(storage.get(Scope::Global, &ROTATION).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn leave_checks_the_guild() {
+    let mut discord = Recorder::default();
+    discord.guilds.push(Guild { id: GuildId(GUILD), name: "test guild".to_owned(), members: 2 });
+
+    command::leave_guild(&discord, "guild").await.unwrap();
+    command::leave_guild(&discord, "42").await.unwrap();
+    command::leave_guild(&discord, &GUILD.to_string()).await.unwrap();
+
+    assert_eq!(discord.replies(), [
+        "\"guild\" parameter must be GUILD_ID!".to_owned(),
+        "not in guild 42!".to_owned(),
+        format!("leaving test guild ({GUILD})..."),
+    ]);
+    assert_eq!(*discord.left.lock().unwrap(), [GuildId(GUILD)]);
+}
+
+#[tokio::test]
+async fn shards_lists_each_shard_of_this_process() {
+    let discord = Recorder {
+        shards: vec![
+            Shard { id: 2, stage: "connected".to_owned(), latency: Some(Duration::from_millis(42)), guilds: 120 },
+            Shard { id: 3, stage: "resuming".to_owned(), latency: None, guilds: 98 },
+        ],
+        shard_total: 4,
+        ..Default::default()
+    };
+
+    command::list_shards(&discord).await.unwrap();
+
+    assert_eq!(discord.replies(), [
+        "```\nrunning 2 of 4 shards\n#2 connected 42ms 120 guilds\n#3 resuming ... 98 guilds\n```",
+    ]);
+}
+
+#[tokio::test]
+async fn shards_reports_the_running_shard() {
+    let harness = Harness::start().await;
+
+    let reply = harness.command("!owner shards").await;
+    assert!(reply.starts_with("```\nrunning 1 of 1 shards\n#0 "), "{reply}");
+}
+
+#[tokio::test]
+async fn shards_restart_only_runs_for_shards_of_this_process() {
+    let discord = Recorder {
+        shards: vec![Shard { id: 2, stage: "connected".to_owned(), latency: None, guilds: 120 }],
+        shard_total: 4,
+        ..Default::default()
+    };
+
+    command::restart_shard(&discord, "shard").await.unwrap();
+    command::restart_shard(&discord, "3").await.unwrap();
+    command::restart_shard(&discord, "2").await.unwrap();
+
+    assert_eq!(discord.replies(), [
+        "\"shard\" parameter must be SHARD_ID!",
+        "shard #3 is not run by this process!",
+        "restarting shard #2...",
+    ]);
+    assert_eq!(*discord.restarted.lock().unwrap(), [2]);
+}
+
+#[tokio::test]
+async fn shards_restart_reconnects_the_shard() {
+    let harness = Harness::start().await;
+
+    assert_eq!(harness.command("!owner shards restart 0").await, "restarting shard #0...");
+}
+
+#[tokio::test]
+async fn start_rotation_validates_and_replaces_the_rotation() {
+    let discord = Recorder::default();
+    let storage = Storage::new(Memory::default());
+    let owner = owner();
+
+    command::start_rotation(&discord, &storage, &owner, 5, "online").await.unwrap();
+    command::start_rotation(&discord, &storage, &owner, 60, "online | sleeping").await.unwrap();
+    command::start_rotation(&discord, &storage, &owner, 60, "online playing chess | idle").await.unwrap();
+
+    assert_eq!(discord.replies(), [
+        "\"interval\" parameter must be in between 15 and 86400 seconds!",
+        "entries must look like \"STATUS [ACTIVITY] [TEXT] | ...\"!",
+        "rotating 2 presences every 60 seconds...",
+    ]);
+    let rotations = discord.rotations.lock().unwrap().len();
+    assert_eq!(rotations, 1);
+    assert!(owner.lock().await.rotation.is_some());
+
+    command::list_rotation(&discord, &storage).await.unwrap();
+    assert_eq!(discord.replies()[3], "rotating every 60 seconds:\n1. online playing chess\n2. idle");
+}
+
+#[tokio::test]
+async fn stop_rotation_goes_back_to_the_stored_presence() {
+    let discord = Recorder::default();
+    let storage = Storage::new(Memory::default());
+    let owner = owner();
+    let presence = Presence { status: StatusType::Idle, activity: None, name: None };
+
+    command::stop_rotation(&discord, &storage, &base::Config::default(), &owner).await.unwrap();
+    command::start_rotation(&discord, &storage, &owner, 60, "online | dnd").await.unwrap();
+    storage.set(Scope::Global, &PRESENCE, &presence).await.unwrap();
+    command::stop_rotation(&discord, &storage, &base::Config::default(), &owner).await.unwrap();
+
+    assert_eq!(discord.replies(), [
+        "no presence rotation is running!",
+        "rotating 2 presences every 60 seconds...",
+        "stopping presence rotation...",
+    ]);
+    let presences = discord.presences.lock().unwrap().iter().map(Presence::describe).collect::<Vec<String>>();
+    assert_eq!(presences, ["idle"]);
+    assert!(owner.lock().await.rotation.is_none());
+    assert!(storage.get(Scope::Global, &ROTATION).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn register_commands_applies_the_diff_to_the_scope() {
+    let discord = Recorder {
+        guild_id: Some(GuildId(GUILD)),
+        commands: vec![json!({ "name": "help", "description": "Show help", "options": [] })],
+        ..Default::default()
+    };
+
+    command::register_commands(&discord, None, "somewhere").await.unwrap();
+    command::register_commands(&discord, None, "local").await.unwrap();
+    command::register_commands(&discord, None, &GUILD.to_string()).await.unwrap();
+    command::unregister_commands(&discord, "local").await.unwrap();
+
+    let replies = discord.replies();
+    assert_eq!(replies[0], "\"scope\" parameter must be \"local\", \"global\", or GUILD_ID!");
+    assert!(replies[1].starts_with(&format!("registering commands for {GUILD}...\n```diff\n+")), "{}", replies[1]);
+    assert_eq!(replies[2..], [
+        "registered commands are already up to date!".to_owned(),
+        format!("unregistering commands for {GUILD}..."),
+    ]);
+    assert_eq!(discord.registered.lock().unwrap().get(&Some(GuildId(GUILD))), Some(&Vec::new()));
+}
+
+#[tokio::test]
+async fn schedule_shutdown_counts_down_until_cancelled() {
+    let discord = Recorder::default();
+    let storage = Storage::new(Memory::default());
+    let owner = owner();
+    let shutdown = base::Shutdown::default();
+
+    command::schedule_shutdown(&discord, &storage, &owner, &shutdown, Some("soon"), false).await.unwrap();
+    command::schedule_shutdown(&discord, &storage, &owner, &shutdown, Some("31d"), false).await.unwrap();
+    command::schedule_shutdown(&discord, &storage, &owner, &shutdown, Some("2h"), true).await.unwrap();
+    command::show_schedule(&discord, &storage).await.unwrap();
+    command::cancel_schedule(&discord, &storage, &owner).await.unwrap();
+    command::cancel_schedule(&discord, &storage, &owner).await.unwrap();
+
+    let replies = discord.replies();
+    assert!(replies[0].starts_with("\"when\" parameter must be a duration"), "{}", replies[0]);
+    assert_eq!(replies[1], "\"when\" parameter must be in the next 30 days!");
+    assert!(replies[2].starts_with("scheduled restart in about 2h"), "{}", replies[2]);
+    assert!(replies[3].starts_with("restart in about "), "{}", replies[3]);
+    assert_eq!(replies[4..], ["cancelled scheduled restart!", "no shutdown is scheduled!"]);
+    let restarts = discord.schedules.lock().unwrap().iter().map(|schedule| schedule.restart).collect::<Vec<bool>>();
+    assert_eq!(restarts, [true]);
+    assert!(owner.lock().await.shutdown.is_none());
+    assert!(!shutdown.is_triggered());
+}
+
+#[tokio::test]
+async fn show_status_reports_the_cache_and_shards() {
+    let discord = Recorder {
+        cache_size: CacheSize { guilds: 3, channels: 40, users: 500 },
+        shards: vec![
+            Shard { id: 0, stage: "connected".to_owned(), latency: Some(Duration::from_millis(42)), guilds: 2 },
+            Shard { id: 1, stage: "resuming".to_owned(), latency: None, guilds: 1 },
+        ],
+        shard_total: 2,
+        ..Default::default()
+    };
+
+    command::show_status(&discord, std::time::Instant::now(), 4).await.unwrap();
+
+    let reply = &discord.replies()[0];
+    assert!(reply.contains("\nawaited on shutdown: 4 commands and tasks\n"), "{reply}");
+    assert!(reply.contains("\ncache: 3 guilds, 40 channels, 500 users\nshards: 2 of 2\n"), "{reply}");
+    assert!(reply.ends_with("\n#0 connected 42ms\n#1 resuming ...\n```"), "{reply}");
+}
+
+#[tokio::test]
+async fn list_guilds_paginates() {
+    let mut discord = Recorder::default();
+
+    command::list_guilds(&discord).await.unwrap();
+    discord.guilds = (1..=25)
+        .map(|id| Guild { id: GuildId(id), name: format!("guild {id}"), members: 100 - id })
+        .collect();
+    command::list_guilds(&discord).await.unwrap();
+
+    assert_eq!(discord.replies(), ["not in any guild!"]);
+    let pages = discord.pages.lock().unwrap().clone();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].len(), 2);
+    assert!(pages[0][0].starts_with("`1` guild 1 (99 members)\n`2` guild 2 (98 members)"), "{}", pages[0][0]);
+    assert_eq!(pages[0][1].lines().count(), 5);
+}
+
+fn user(id: u64) -> User {
+    serde_json::from_value(json!({ "id": id.to_string(), "username": "user", "discriminator": "0001", "avatar": null })).unwrap()
+}
+
+#[tokio::test]
+async fn admins_are_added_listed_and_removed() {
+    let discord = Recorder::default();
+    let storage = Storage::new(Memory::default());
+    let mut config = base::Config::default();
+    config.core.bot_admins = vec![UserId(OWNER)];
+
+    command::add_admin(&discord, &storage, &user(MEMBER)).await.unwrap();
+    command::add_admin(&discord, &storage, &user(MEMBER)).await.unwrap();
+    command::list_admins(&discord, &storage, &config).await.unwrap();
+    command::remove_admin(&discord, &storage, &config, &user(OWNER)).await.unwrap();
+    command::remove_admin(&discord, &storage, &config, &user(MEMBER)).await.unwrap();
+    command::remove_admin(&discord, &storage, &config, &user(MEMBER)).await.unwrap();
+
+    assert_eq!(discord.replies(), [
+        "added user#0001 as bot admin!".to_owned(),
+        "user#0001 is already a bot admin!".to_owned(),
+        format!("bot admins:\n<@{OWNER}> (config)\n<@{MEMBER}>"),
+        "user#0001 is a bot admin through the config, remove them there!".to_owned(),
+        "removed user#0001 from bot admins!".to_owned(),
+        "user#0001 is not a bot admin!".to_owned(),
+    ]);
+    assert_eq!(storage.get(Scope::Global, &BOT_ADMINS).await.unwrap(), Some(Vec::new()));
+}
@@ -0,0 +1,118 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use serde_json::json;
+use crate::server::State;
+
+/// How serenity displays a connected shard. poise doesn't re-export its
+/// `ConnectionStage`, so stages are told apart by name.
+const CONNECTED: &str = "connected";
+
+pub async fn serve(address: SocketAddr, state: Arc<State>) {
+    let service = make_service_fn(move |_| {
+        let state = state.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| route(request, state.clone())))
+        }
+    });
+
+    let message = "serving http";
+    let address_text = address.to_string();
+    tracing::info!(message, address = address_text);
+
+    if let Err(error) = Server::bind(&address).serve(service).await {
+        let message = "error serving http";
+        let error = error.to_string();
+        tracing::error!(message, error);
+    }
+}
+
+async fn route(request: Request<Body>, state: Arc<State>) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/healthz") => Response::builder()
+            .header(CONTENT_TYPE, "text/plain")
+            .body(Body::from("ok")),
+        (&Method::GET, "/readyz") => {
+            let (ready, body) = readiness(&state).await;
+            let status = match ready {
+                true => StatusCode::OK,
+                false => StatusCode::SERVICE_UNAVAILABLE,
+            };
+            Response::builder()
+                .status(status)
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+        }
+        (&Method::GET, "/metrics") => match state.metrics.render() {
+            Ok(body) => Response::builder()
+                .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+                .body(Body::from(body)),
+            Err(error) => {
+                let message = "error rendering metrics";
+                tracing::error!(message, error);
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::empty())
+            }
+        },
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty()),
+    };
+
+    Ok(response.unwrap())
+}
+
+async fn readiness(state: &State) -> (bool, serde_json::Value) {
+    let runners = state.shard_manager.lock().await.runners.clone();
+    let runners = runners.lock().await;
+
+    let mut shards = runners
+        .iter()
+        .map(|(shard_id, runner)| {
+            let latency = runner.latency.map(|latency| latency.as_millis() as u64);
+            let stage = runner.stage.to_string();
+            let shard = json!({
+                "id": shard_id.0,
+                "connected": stage == CONNECTED,
+                "stage": stage,
+                "latency": latency,
+            });
+            (shard_id.0, shard)
+        })
+        .collect::<Vec<_>>();
+    shards.sort_by_key(|(id, _)| *id);
+
+    // the cache learns the recommended count from the first `Ready`
+    let expected = state.shards.unwrap_or_else(|| state.cache.shard_count());
+    let stages = runners.values().map(|runner| runner.stage.to_string()).collect::<Vec<String>>();
+    let ready = ready(state.health.is_setup(), &stages, expected as usize);
+
+    let shards = shards.into_iter().map(|(_, shard)| shard).collect::<Vec<_>>();
+    let body = json!({ "ready": ready, "shards": shards });
+
+    (ready, body)
+}
+
+/// Ready once data is built and every shard of this process is connected, so
+/// the bot stops being ready as soon as one of them drops.
+pub fn ready(setup: bool, stages: &[String], expected: usize) -> bool {
+    setup && !stages.is_empty() && stages.len() >= expected && stages.iter().all(|stage| stage == CONNECTED)
+}
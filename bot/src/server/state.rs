@@ -0,0 +1,55 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use poise::serenity_prelude::{Cache, ShardManager};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use crate::metrics::Metrics;
+
+/// Everything the routes read from, shared by every connection.
+pub struct State {
+    pub metrics: Arc<Metrics>,
+    pub health: Arc<Health>,
+    pub shard_manager: Arc<Mutex<ShardManager>>,
+    pub cache: Arc<Cache>,
+    /// Shards run by this process, learned from the first `Ready` when autosharding.
+    pub shards: Option<u64>,
+}
+
+/// Set once the first `Ready` went through the framework and data is built,
+/// from then on the shard stages decide whether the bot is ready.
+#[derive(Default)]
+pub struct Health {
+    setup: AtomicBool,
+}
+
+impl Health {
+    pub fn mark_setup(&self) {
+        self.setup.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_setup(&self) -> bool {
+        self.setup.load(Ordering::Relaxed)
+    }
+}
+
+/// The built-in HTTP server (metrics and health probes) is disabled unless an address is set.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub address: Option<SocketAddr>,
+}
@@ -0,0 +1,40 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::server::route;
+
+fn stages(stages: &[&str]) -> Vec<String> {
+    stages.iter().map(|stage| stage.to_string()).collect()
+}
+
+#[test]
+fn ready_needs_setup_and_every_shard_connected() {
+    assert!(route::ready(true, &stages(&["connected", "connected"]), 2));
+    assert!(!route::ready(false, &stages(&["connected", "connected"]), 2));
+    assert!(!route::ready(true, &stages(&["connected", "resuming"]), 2));
+}
+
+#[test]
+fn ready_waits_for_every_expected_shard() {
+    assert!(!route::ready(true, &stages(&["connected"]), 2));
+    // autosharding before the first `Ready` knows of no shard yet
+    assert!(!route::ready(true, &[], 0));
+}
+
+#[test]
+fn ready_is_lost_once_shards_disconnect() {
+    assert!(route::ready(true, &stages(&["connected"]), 1));
+    assert!(!route::ready(true, &stages(&["disconnected"]), 1));
+    assert!(!route::ready(true, &stages(&["connecting"]), 1));
+}
@@ -0,0 +1,66 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use crate::helper::Result;
+
+/// Raw key-value persistence, values are stored as serialized JSON.
+///
+/// `kind` is the scope of the setting (`global`, `guild`, `user`) and `id`
+/// the snowflake it belongs to, `0` for global settings.
+pub trait Backend: Send + Sync {
+    fn migrate(&self) -> Result<()>;
+    fn get(&self, kind: &str, id: u64, key: &str) -> Result<Option<String>>;
+    fn set(&self, kind: &str, id: u64, key: &str, value: &str) -> Result<()>;
+    fn remove(&self, kind: &str, id: u64, key: &str) -> Result<()>;
+    /// Persists anything still buffered, called once before exiting.
+    fn flush(&self) -> Result<()>;
+}
+
+#[derive(Default)]
+pub struct Memory {
+    entries: Mutex<HashMap<(String, u64, String), String>>,
+}
+
+impl Backend for Memory {
+    fn migrate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn get(&self, kind: &str, id: u64, key: &str) -> Result<Option<String>> {
+        let entries = self.entries.lock().unwrap();
+        let value = entries.get(&(kind.to_owned(), id, key.to_owned())).cloned();
+
+        Ok(value)
+    }
+
+    fn set(&self, kind: &str, id: u64, key: &str, value: &str) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert((kind.to_owned(), id, key.to_owned()), value.to_owned());
+
+        Ok(())
+    }
+
+    fn remove(&self, kind: &str, id: u64, key: &str) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(&(kind.to_owned(), id, key.to_owned()));
+
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
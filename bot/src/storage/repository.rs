@@ -0,0 +1,153 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use poise::serenity_prelude::GuildId;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use crate::helper::Result;
+use crate::storage::{Backend, Config, Engine, Memory, Sqlite};
+
+/// Who a setting belongs to.
+#[derive(Clone, Copy)]
+pub enum Scope {
+    Global,
+    Guild(GuildId),
+}
+
+impl Scope {
+    fn split(self) -> (&'static str, u64) {
+        match self {
+            Scope::Global => ("global", 0),
+            Scope::Guild(guild_id) => ("guild", guild_id.0),
+        }
+    }
+}
+
+/// A named setting with the type it is stored as, declared once per module:
+///
+/// ```ignore
+/// const PREFIXES: Key<Vec<String>> = Key::cached("admin.prefixes");
+/// ```
+pub struct Key<T> {
+    name: &'static str,
+    cached: bool,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T> Key<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Key { name, cached: false, value: PhantomData }
+    }
+
+    /// A setting read on hot paths such as every message, kept in memory
+    /// after the first read and updated on every write.
+    pub const fn cached(name: &'static str) -> Self {
+        Key { name, cached: true, value: PhantomData }
+    }
+}
+
+type Entries = HashMap<(&'static str, u64, &'static str), Option<String>>;
+
+/// Typed access to persisted settings, cheap to clone.
+#[derive(Clone)]
+pub struct Storage {
+    backend: Arc<dyn Backend>,
+    cache: Arc<Mutex<Entries>>,
+}
+
+impl Storage {
+    pub fn open(config: &Config) -> Result<Self> {
+        let storage = match config.engine {
+            Engine::Sqlite => Storage::new(Sqlite::open(&config.path)?),
+            Engine::Memory => Storage::new(Memory::default()),
+        };
+
+        Ok(storage)
+    }
+
+    pub fn new(backend: impl Backend + 'static) -> Self {
+        Storage { backend: Arc::new(backend), cache: Arc::default() }
+    }
+
+    pub async fn migrate(&self) -> Result<()> {
+        let backend = self.backend.clone();
+        tokio::task::spawn_blocking(move || backend.migrate()).await?
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, scope: Scope, key: &Key<T>) -> Result<Option<T>> {
+        let backend = self.backend.clone();
+        let (kind, id) = scope.split();
+        let name = key.name;
+
+        let cached = match key.cached {
+            true => self.cache.lock().unwrap().get(&(kind, id, name)).cloned(),
+            false => None,
+        };
+
+        let value = match cached {
+            Some(value) => value,
+            None => {
+                let value = tokio::task::spawn_blocking(move || backend.get(kind, id, name)).await??;
+                if key.cached {
+                    // a write that raced this read already holds the newer value
+                    self.cache.lock().unwrap().entry((kind, id, name)).or_insert_with(|| value.clone());
+                }
+                value
+            }
+        };
+
+        let value = value
+            .map(|value| serde_json::from_str(&value))
+            .transpose()
+            .map_err(|error| format!("error decoding stored `{name}`: {error}"))?;
+
+        Ok(value)
+    }
+
+    pub async fn set<T: Serialize>(&self, scope: Scope, key: &Key<T>, value: &T) -> Result<()> {
+        let backend = self.backend.clone();
+        let (kind, id) = scope.split();
+        let name = key.name;
+        let value = serde_json::to_string(value)?;
+
+        let stored = value.clone();
+        tokio::task::spawn_blocking(move || backend.set(kind, id, name, &stored)).await??;
+        if key.cached {
+            self.cache.lock().unwrap().insert((kind, id, name), Some(value));
+        }
+
+        Ok(())
+    }
+
+    pub async fn remove<T>(&self, scope: Scope, key: &Key<T>) -> Result<()> {
+        let backend = self.backend.clone();
+        let (kind, id) = scope.split();
+        let name = key.name;
+
+        tokio::task::spawn_blocking(move || backend.remove(kind, id, name)).await??;
+        if key.cached {
+            self.cache.lock().unwrap().insert((kind, id, name), None);
+        }
+
+        Ok(())
+    }
+
+    pub async fn flush(&self) -> Result<()> {
+        let backend = self.backend.clone();
+        tokio::task::spawn_blocking(move || backend.flush()).await?
+    }
+}
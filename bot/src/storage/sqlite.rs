@@ -0,0 +1,116 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+use std::sync::Mutex;
+use rusqlite::{Connection, OptionalExtension};
+use crate::helper::Result;
+use crate::storage::Backend;
+
+/// Schema migrations, applied in order and tracked with `PRAGMA user_version`.
+///
+/// Never edit an existing entry, append a new one instead.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE settings (
+        kind TEXT NOT NULL,
+        id INTEGER NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (kind, id, key)
+    );",
+];
+
+pub struct Sqlite {
+    connection: Mutex<Connection>,
+}
+
+impl Sqlite {
+    pub fn open(path: &str) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let connection = Connection::open(path)?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+
+        Ok(Sqlite { connection: Mutex::new(connection) })
+    }
+}
+
+impl Backend for Sqlite {
+    fn migrate(&self) -> Result<()> {
+        let mut connection = self.connection.lock().unwrap();
+        let version: usize = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+        if version > MIGRATIONS.len() {
+            let message = format!("storage schema version {version} is newer than this build supports");
+            return Err(message.into())
+        }
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let transaction = connection.transaction()?;
+            transaction.execute_batch(migration)?;
+            transaction.pragma_update(None, "user_version", index + 1)?;
+            transaction.commit()?;
+
+            let message = "storage schema migrated";
+            let version = index + 1;
+            tracing::info!(message, version);
+        }
+
+        Ok(())
+    }
+
+    fn get(&self, kind: &str, id: u64, key: &str) -> Result<Option<String>> {
+        let connection = self.connection.lock().unwrap();
+        let value = connection
+            .query_row(
+                "SELECT value FROM settings WHERE kind = ?1 AND id = ?2 AND key = ?3",
+                (kind, id, key),
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(value)
+    }
+
+    fn set(&self, kind: &str, id: u64, key: &str, value: &str) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO settings (kind, id, key, value) VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT (kind, id, key) DO UPDATE SET value = excluded.value",
+            (kind, id, key, value),
+        )?;
+
+        Ok(())
+    }
+
+    fn remove(&self, kind: &str, id: u64, key: &str) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "DELETE FROM settings WHERE kind = ?1 AND id = ?2 AND key = ?3",
+            (kind, id, key),
+        )?;
+
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        // folds the write-ahead log back into the database file
+        connection.pragma_update(None, "wal_checkpoint", "TRUNCATE")?;
+
+        Ok(())
+    }
+}
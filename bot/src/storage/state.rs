@@ -0,0 +1,50 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{Deserialize, Serialize};
+use crate::helper::Result;
+
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub engine: Engine,
+    pub path: String,
+}
+
+/// `memory` keeps everything in process, for tests and throwaway instances.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    Sqlite,
+    Memory,
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<()> {
+        if self.engine == Engine::Sqlite && self.path.is_empty() {
+            return Err("`storage.path` must not be empty".into())
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            engine: Engine::Sqlite,
+            path: "data/bot.sqlite".to_owned(),
+        }
+    }
+}
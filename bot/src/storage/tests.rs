@@ -0,0 +1,154 @@
+// Copyright 2023 cytheworker
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use poise::serenity_prelude::GuildId;
+use rusqlite::Connection;
+use crate::helper::Result;
+use crate::storage::{Backend, Key, Memory, Scope, Sqlite, Storage};
+
+const PREFIXES: Key<Vec<String>> = Key::new("test.prefixes");
+const CACHED: Key<Vec<String>> = Key::cached("test.cached");
+
+/// A database file of its own for each test, removed beforehand in case a
+/// previous run left it behind.
+fn path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("bot-{}-{name}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn user_version(path: &PathBuf) -> usize {
+    let connection = Connection::open(path).unwrap();
+    connection.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap()
+}
+
+async fn round_trip(storage: Storage) {
+    let guild = Scope::Guild(GuildId(1));
+    let other = Scope::Guild(GuildId(2));
+    let prefixes = vec!["!".to_owned(), "?".to_owned()];
+
+    assert_eq!(storage.get(guild, &PREFIXES).await.unwrap(), None);
+
+    storage.set(guild, &PREFIXES, &prefixes).await.unwrap();
+    assert_eq!(storage.get(guild, &PREFIXES).await.unwrap(), Some(prefixes.clone()));
+    assert_eq!(storage.get(other, &PREFIXES).await.unwrap(), None);
+    assert_eq!(storage.get(Scope::Global, &PREFIXES).await.unwrap(), None);
+
+    storage.set(guild, &PREFIXES, &vec!["$".to_owned()]).await.unwrap();
+    assert_eq!(storage.get(guild, &PREFIXES).await.unwrap(), Some(vec!["$".to_owned()]));
+
+    storage.remove(guild, &PREFIXES).await.unwrap();
+    assert_eq!(storage.get(guild, &PREFIXES).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn memory_round_trips() {
+    round_trip(Storage::new(Memory::default())).await;
+}
+
+#[tokio::test]
+async fn sqlite_round_trips() {
+    let path = path("round-trip");
+    let storage = Storage::new(Sqlite::open(path.to_str().unwrap()).unwrap());
+    storage.migrate().await.unwrap();
+
+    round_trip(storage.clone()).await;
+
+    storage.set(Scope::Global, &PREFIXES, &vec!["!".to_owned()]).await.unwrap();
+    storage.flush().await.unwrap();
+    drop(storage);
+
+    let reopened = Storage::new(Sqlite::open(path.to_str().unwrap()).unwrap());
+    assert_eq!(reopened.get(Scope::Global, &PREFIXES).await.unwrap(), Some(vec!["!".to_owned()]));
+}
+
+#[test]
+fn sqlite_migrates_once_and_tracks_user_version() {
+    let path = path("migrate");
+    let sqlite = Sqlite::open(path.to_str().unwrap()).unwrap();
+    assert_eq!(user_version(&path), 0);
+
+    sqlite.migrate().unwrap();
+    assert_eq!(user_version(&path), 1);
+
+    // already applied migrations are skipped rather than failing on the
+    // existing tables
+    sqlite.migrate().unwrap();
+    assert_eq!(user_version(&path), 1);
+}
+
+#[test]
+fn sqlite_refuses_a_newer_schema() {
+    let path = path("newer");
+    let sqlite = Sqlite::open(path.to_str().unwrap()).unwrap();
+    Connection::open(&path).unwrap().pragma_update(None, "user_version", 99).unwrap();
+
+    let error = sqlite.migrate().unwrap_err();
+    assert!(error.to_string().contains("newer than this build supports"), "{error}");
+}
+
+#[tokio::test]
+async fn cached_keys_follow_writes() {
+    let storage = Storage::new(Memory::default());
+    let guild = Scope::Guild(GuildId(1));
+
+    assert_eq!(storage.get(guild, &CACHED).await.unwrap(), None);
+
+    storage.set(guild, &CACHED, &vec!["!".to_owned()]).await.unwrap();
+    assert_eq!(storage.get(guild, &CACHED).await.unwrap(), Some(vec!["!".to_owned()]));
+
+    storage.remove(guild, &CACHED).await.unwrap();
+    assert_eq!(storage.get(guild, &CACHED).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn cached_keys_are_read_from_memory() {
+    let backend = Arc::new(Memory::default());
+    let storage = Storage::new(Shared(backend.clone()));
+    let guild = Scope::Guild(GuildId(1));
+
+    storage.set(guild, &CACHED, &vec!["!".to_owned()]).await.unwrap();
+    // written behind the storage's back, so only a read that skips the
+    // cache would see it
+    backend.set("guild", 1, "test.cached", "[\"?\"]").unwrap();
+
+    assert_eq!(storage.get(guild, &CACHED).await.unwrap(), Some(vec!["!".to_owned()]));
+}
+
+/// A backend the test keeps a handle to.
+struct Shared(Arc<Memory>);
+
+impl Backend for Shared {
+    fn migrate(&self) -> Result<()> {
+        self.0.migrate()
+    }
+
+    fn get(&self, kind: &str, id: u64, key: &str) -> Result<Option<String>> {
+        self.0.get(kind, id, key)
+    }
+
+    fn set(&self, kind: &str, id: u64, key: &str, value: &str) -> Result<()> {
+        self.0.set(kind, id, key, value)
+    }
+
+    fn remove(&self, kind: &str, id: u64, key: &str) -> Result<()> {
+        self.0.remove(kind, id, key)
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.0.flush()
+    }
+}